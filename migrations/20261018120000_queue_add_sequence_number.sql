@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE queues ADD COLUMN sequence_number INTEGER NOT NULL DEFAULT 0;
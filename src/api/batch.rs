@@ -0,0 +1,130 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use super::{error::SqsError, helpers};
+
+/// Every batch action accepts at most 10 entries
+pub const MAX_BATCH_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchEntry {
+    pub id: String,
+    pub fields: HashMap<String, String>,
+}
+
+impl BatchEntry {
+    pub fn get(&self, field: &str) -> Option<&String> {
+        self.fields.get(field)
+    }
+}
+
+/// Failed entry of a batch response
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BatchResultErrorEntry {
+    id: String,
+    sender_fault: bool,
+    code: String,
+    message: String,
+}
+
+impl BatchResultErrorEntry {
+    pub fn new(id: &str, err: &SqsError) -> Self {
+        BatchResultErrorEntry {
+            id: id.to_string(),
+            sender_fault: err.is_sender_fault(),
+            code: err.code.to_string(),
+            message: err.message.clone(),
+        }
+    }
+}
+
+/// Read the `<prefix>.N.*` entries of a batch request and validate the batch as a whole.
+/// Errors here fail the entire request, per-entry errors are up to the action.
+pub fn extract_batch_entries(
+    prefix: &str,
+    extra: &HashMap<String, String>,
+) -> Result<Vec<BatchEntry>, SqsError> {
+    let entries = helpers::extract_entries(prefix, extra);
+    if entries.is_empty() {
+        return Err(SqsError::empty_batch_request());
+    }
+    if entries.len() > MAX_BATCH_ENTRIES {
+        return Err(SqsError::too_many_entries_in_batch_request(
+            MAX_BATCH_ENTRIES,
+        ));
+    }
+
+    let mut ids = HashSet::new();
+    let mut batch = Vec::with_capacity(entries.len());
+    for mut fields in entries {
+        let id = fields.remove("Id").unwrap_or_default();
+        if !is_valid_entry_id(&id) {
+            return Err(SqsError::invalid_batch_entry_id(&id));
+        }
+        if !ids.insert(id.clone()) {
+            return Err(SqsError::batch_entry_ids_not_distinct(&id));
+        }
+
+        batch.push(BatchEntry { id, fields });
+    }
+
+    Ok(batch)
+}
+
+fn is_valid_entry_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 80
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch_request(ids: &[&str]) -> HashMap<String, String> {
+        let mut extra = HashMap::new();
+        for (i, id) in ids.iter().enumerate() {
+            extra.insert(format!("Entry.{}.Id", i + 1), id.to_string());
+            extra.insert(
+                format!("Entry.{}.ReceiptHandle", i + 1),
+                format!("handle-{}", i),
+            );
+        }
+        extra
+    }
+
+    #[test]
+    fn test_extract_batch_entries() {
+        let entries = extract_batch_entries("Entry", &batch_request(&["a", "b"])).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, "a");
+        assert_eq!(
+            entries[0].get("ReceiptHandle"),
+            Some(&"handle-0".to_string())
+        );
+        assert_eq!(entries[0].get("Id"), None);
+    }
+
+    #[test]
+    fn test_extract_batch_entries_errors() {
+        let err = extract_batch_entries("Entry", &HashMap::new()).unwrap_err();
+        assert_eq!(err.code, "AWS.SimpleQueueService.EmptyBatchRequest");
+
+        let ids: Vec<String> = (0..11).map(|i| format!("id{}", i)).collect();
+        let ids: Vec<&str> = ids.iter().map(|id| id.as_str()).collect();
+        let err = extract_batch_entries("Entry", &batch_request(&ids)).unwrap_err();
+        assert_eq!(
+            err.code,
+            "AWS.SimpleQueueService.TooManyEntriesInBatchRequest"
+        );
+
+        let err = extract_batch_entries("Entry", &batch_request(&["a", "a"])).unwrap_err();
+        assert_eq!(err.code, "AWS.SimpleQueueService.BatchEntryIdsNotDistinct");
+
+        let err = extract_batch_entries("Entry", &batch_request(&["not valid"])).unwrap_err();
+        assert_eq!(err.code, "AWS.SimpleQueueService.InvalidBatchEntryId");
+    }
+}
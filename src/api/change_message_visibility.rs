@@ -0,0 +1,102 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+use super::{error::SqsError, helpers};
+use crate::{queue::MAX_VISIBILITY_TIMEOUT, AppState};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeMessageVisibilityParams {
+    queue_url: Option<String>,
+    receipt_handle: String,
+    visibility_timeout: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeMessageVisibilityResponse {
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Parse a visibility timeout, it has to be between 0 seconds and 12 hours
+pub(super) fn parse_visibility_timeout(value: &str) -> Result<Duration, SqsError> {
+    match value.trim().parse::<u64>() {
+        Ok(seconds) if seconds <= MAX_VISIBILITY_TIMEOUT => Ok(Duration::from_secs(seconds)),
+        _ => Err(SqsError::invalid_parameter_value(&format!(
+            "Value {} for parameter VisibilityTimeout is invalid. Reason: Must be between 0 and {}.",
+            value, MAX_VISIBILITY_TIMEOUT
+        ))),
+    }
+}
+
+/// Change the visibility timeout of an in-flight message
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<ChangeMessageVisibilityParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let timeout = match parse_visibility_timeout(&payload.visibility_timeout) {
+        Ok(timeout) => timeout,
+        Err(e) => return e.response(),
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let mut writer = app_state.queues.lock().await;
+    let queue = match (*writer).get_mut(&queue_name) {
+        Some(queue) => queue,
+        None => return SqsError::non_existent_queue().response(),
+    };
+
+    if let Err(e) = queue.change_visibility(&payload.receipt_handle, timeout) {
+        return SqsError::from_receipt_error(e, &payload.receipt_handle).response();
+    }
+
+    let response = ChangeMessageVisibilityResponse {
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_visibility_timeout() {
+        assert_eq!(parse_visibility_timeout("0"), Ok(Duration::ZERO));
+        assert_eq!(
+            parse_visibility_timeout("43200"),
+            Ok(Duration::from_secs(43_200))
+        );
+        assert!(parse_visibility_timeout("43201").is_err());
+        assert!(parse_visibility_timeout("-1").is_err());
+        assert!(parse_visibility_timeout("abc").is_err());
+    }
+}
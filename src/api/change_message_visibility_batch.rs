@@ -0,0 +1,176 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+use super::{
+    batch::{self, BatchResultErrorEntry},
+    change_message_visibility::parse_visibility_timeout,
+    error::SqsError,
+    helpers,
+};
+use crate::AppState;
+
+const ENTRY_PREFIX: &str = "ChangeMessageVisibilityBatchRequestEntry";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeMessageVisibilityBatchParams {
+    queue_url: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeMessageVisibilityBatchResponse {
+    change_message_visibility_batch_result: ChangeMessageVisibilityBatchResult,
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeMessageVisibilityBatchResult {
+    #[serde(rename = "ChangeMessageVisibilityBatchResultEntry")]
+    successful: Vec<ChangeMessageVisibilityBatchResultEntry>,
+    #[serde(rename = "BatchResultErrorEntry")]
+    failed: Vec<BatchResultErrorEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeMessageVisibilityBatchResultEntry {
+    id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Change the visibility timeout of up to 10 in-flight messages, every entry succeeds or fails on its own
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<ChangeMessageVisibilityBatchParams>(payload)
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let entries = match batch::extract_batch_entries(ENTRY_PREFIX, &payload.extra) {
+        Ok(entries) => entries,
+        Err(e) => return e.response(),
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let mut writer = app_state.queues.lock().await;
+    let queue = match (*writer).get_mut(&queue_name) {
+        Some(queue) => queue,
+        None => return SqsError::non_existent_queue().response(),
+    };
+
+    let mut result = ChangeMessageVisibilityBatchResult {
+        successful: vec![],
+        failed: vec![],
+    };
+    for entry in entries {
+        let timeout = entry
+            .get("VisibilityTimeout")
+            .map(|value| parse_visibility_timeout(value))
+            .unwrap_or(Ok(std::time::Duration::ZERO));
+
+        let changed = match entry.get("ReceiptHandle") {
+            Some(receipt_handle) => timeout.and_then(|timeout| {
+                queue
+                    .change_visibility(receipt_handle, timeout)
+                    .map_err(|e| SqsError::from_receipt_error(e, receipt_handle))
+            }),
+            None => Err(SqsError::missing_parameter("ReceiptHandle")),
+        };
+        match changed {
+            Ok(_) => result
+                .successful
+                .push(ChangeMessageVisibilityBatchResultEntry { id: entry.id }),
+            Err(e) => result
+                .failed
+                .push(BatchResultErrorEntry::new(&entry.id, &e)),
+        }
+    }
+
+    let response = ChangeMessageVisibilityBatchResponse {
+        change_message_visibility_batch_result: result,
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_change_message_visibility_batch() {
+        let mut queue = crate::queue::Queue::new();
+        queue.push(crate::queue::Message::new("id", "message_body"));
+        let receipt_handle = queue.receive(None).unwrap().0;
+        let app_state = Arc::new(AppState {
+            db_pool: sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap(),
+            host_name: "http://localhost:9090".to_string(),
+            enforce_quotas: false,
+            queues: Arc::new(tokio::sync::Mutex::new(HashMap::from([(
+                "test".to_string(),
+                queue,
+            )]))),
+        });
+
+        let payload = web::Bytes::from(
+            serde_urlencoded::to_string([
+                ("Action", "ChangeMessageVisibilityBatch"),
+                ("QueueUrl", "http://localhost:9090/test"),
+                ("ChangeMessageVisibilityBatchRequestEntry.1.Id", "valid"),
+                (
+                    "ChangeMessageVisibilityBatchRequestEntry.1.ReceiptHandle",
+                    &receipt_handle,
+                ),
+                ("ChangeMessageVisibilityBatchRequestEntry.2.Id", "missing"),
+                (
+                    "ChangeMessageVisibilityBatchRequestEntry.2.VisibilityTimeout",
+                    "0",
+                ),
+            ])
+            .unwrap(),
+        );
+        let response = process(app_state.clone(), &payload, "/", false).await;
+        assert_eq!(response.status(), 200);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("<ChangeMessageVisibilityBatchResultEntry><Id>valid</Id>"));
+        assert!(body.contains("<Id>missing</Id><SenderFault>true</SenderFault><Code>MissingParameter</Code><Message>The request must contain the parameter ReceiptHandle.</Message>"));
+
+        let queues = app_state.queues.lock().await;
+        assert_eq!(
+            queues.get("test").unwrap().approximate_number_of_messages(),
+            1
+        );
+    }
+}
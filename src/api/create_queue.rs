@@ -1,15 +1,17 @@
-use super::helpers;
+use super::{error::SqsError, helpers};
 use crate::AppState;
 use actix_web::{web, HttpResponse};
-use regex::{Regex, RegexBuilder};
+use regex::RegexBuilder;
 use serde::{Deserialize, Serialize};
 use tracing::warn;
 use std::{collections::HashMap, sync::Arc};
 
-/// .fifo - for the FIFO queues
-///
+/// Suffix every FIFO queue name ends with
+const FIFO_SUFFIX: &str = ".fifo";
 
-const ATTR_LIST: [&str; 12] = [
+const MAX_QUEUE_NAME_LENGTH: usize = 80;
+
+pub(super) const ATTR_LIST: [&str; 12] = [
     "DelaySeconds",
     "MaximumMessageSize",
     "MessageRetentionPeriod",
@@ -24,7 +26,7 @@ const ATTR_LIST: [&str; 12] = [
     "SqsManagedSseEnabled",
 ];
 
-const ATTR_FIFO: [&str; 4] = [
+pub(super) const ATTR_FIFO: [&str; 4] = [
     "FifoQueue",
     "ContentBasedDeduplication",
     "DeduplicationScope",
@@ -60,31 +62,52 @@ pub struct CreateQueueResult {
 }
 
 impl CreateQueueParams {
-    /// Populate the attributes from the extra hashmap
-    fn create_validate_attributes(&mut self) -> anyhow::Result<()> {
+    /// Whether the queue is created as a FIFO queue, by the `FifoQueue` attribute
+    fn is_fifo(&self) -> bool {
+        self.attributes
+            .iter()
+            .flatten()
+            .any(|attr| attr.name == "FifoQueue" && attr.value == "true")
+    }
+
+    /// Populate the attributes from the extra hashmap and validate them and the queue name.
+    /// FIFO only attributes are only accepted on FIFO queues.
+    fn create_validate_attributes(&mut self) -> Result<(), SqsError> {
         let re = RegexBuilder::new(r"^Attribute\.(\d+)\.(.+)$")
             .case_insensitive(true)
             .build()
             .unwrap();
 
         self.attributes = helpers::extract_from_extra(re, self.extra.clone());
-        if let Some(attrs) = &self.attributes {
-            for attr in attrs {
-                if !ATTR_LIST.contains(&attr.name.as_str()) {
-                    return Err(anyhow::anyhow!("Invalid attribute name: {}", attr.name));
-                }
-            }
+        // `FifoQueue=false` is the same as leaving it out
+        if let Some(attrs) = &mut self.attributes {
+            attrs.retain(|attr| !(attr.name == "FifoQueue" && attr.value == "false"));
         }
 
+        let is_fifo = self.is_fifo();
+        validate_queue_name(&self.queue_name, is_fifo)?;
+        for attr in self.attributes.iter().flatten() {
+            super::set_queue_attributes::validate_attribute(&attr.name, &attr.value, is_fifo)?;
+        }
+        super::set_queue_attributes::validate_throughput_limit(&helpers::get_attrbutes_hashmap(
+            self.attributes.clone(),
+        ))?;
+
         Ok(())
     }
 
-    fn create_tags(&mut self) {
-        let re = RegexBuilder::new(r"^(tags|tag)\.(.+)$")
-            .case_insensitive(true)
-            .build()
-            .unwrap();
-        self.tags = helpers::extract_from_extra(re, self.extra.clone());
+    /// Populate the tags from the `Tag.N.Key` and `Tag.N.Value` parameters
+    fn create_validate_tags(&mut self) -> Result<(), SqsError> {
+        let tags = super::tag_queue::extract_tags(&self.extra);
+        super::tag_queue::validate_tags(&tags, &HashMap::new())?;
+
+        self.tags = Some(
+            tags.into_iter()
+                .map(|(name, value)| helpers::ParamValues { name, value })
+                .collect(),
+        );
+
+        Ok(())
     }
 
     /// Get the attributes as a hashmap
@@ -97,6 +120,38 @@ impl CreateQueueParams {
     }
 }
 
+/// Queue names are 1 to 80 alphanumeric characters, hyphens or underscores. The names of
+/// FIFO queues, and only those, end with `.fifo` which counts toward the length.
+fn validate_queue_name(name: &str, is_fifo: bool) -> Result<(), SqsError> {
+    let base_name = match (name.strip_suffix(FIFO_SUFFIX), is_fifo) {
+        (Some(base_name), true) => base_name,
+        (None, false) => name,
+        (Some(_), false) => {
+            return Err(SqsError::invalid_parameter_value(
+                "The name of a FIFO queue can only end with .fifo when the FifoQueue attribute is set to true.",
+            ))
+        }
+        (None, true) => {
+            return Err(SqsError::invalid_parameter_value(
+                "The name of a FIFO queue can only include alphanumeric characters, hyphens, or underscores, must end with .fifo suffix and be 1 to 80 in length.",
+            ))
+        }
+    };
+
+    let is_valid = !base_name.is_empty()
+        && name.len() <= MAX_QUEUE_NAME_LENGTH
+        && base_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_valid {
+        return Err(SqsError::invalid_parameter_value(
+            "Can only include alphanumeric characters, hyphens, or underscores. 1 to 80 in length",
+        ));
+    }
+
+    Ok(())
+}
+
 /// Create a queue with the given name and attributes
 pub async fn process(
     app_state: Arc<AppState>,
@@ -109,20 +164,49 @@ pub async fn process(
             return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
         }
     };
-    match payload.create_validate_attributes() {
-        Ok(_) => (),
-        Err(e) => {
-            return HttpResponse::BadRequest().body(format!("Failed to validate attributes: {}", e))
-        }
-    };
-    payload.create_tags();
+    if let Err(e) = payload.create_validate_attributes() {
+        return e.response();
+    }
+    if let Err(e) = payload.create_validate_tags() {
+        return e.response();
+    }
 
     let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+    let response = CreateQueueResponse {
+        create_queue_result: CreateQueueResult {
+            queue_url: service.queue_url(&payload.queue_name, None),
+        },
+        reponse_metadata: HashMap::new(),
+    };
+
+    // The lock is held until the new queue is in place so that concurrent creates of the
+    // same name see each other
+    let mut writer = app_state.queues.lock().await;
+    if let Some(queue) = (*writer).get(&payload.queue_name) {
+        // Creating an existing queue returns its URL, unless an attribute is different
+        let attributes = payload.clone().get_attrbutes_hashmap();
+        if let Some(name) = queue.conflicting_attribute(&attributes) {
+            return SqsError::queue_already_exists(name).response();
+        }
+
+        return match quick_xml::se::to_string(&response) {
+            Ok(resp) => HttpResponse::Ok().body(resp),
+            Err(e) => HttpResponse::InternalServerError()
+                .body(format!("Failed to serialize response: {}", e)),
+        };
+    }
+
     let db_result = service
         .create_queue(crate::service::queue::QueueEntity {
             id: None,
             name: payload.queue_name.clone(),
-            queue_type: "Standard".to_string(),
+            queue_type: if payload.is_fifo() {
+                "Fifo"
+            } else {
+                "Standard"
+            }
+            .to_string(),
+            sequence_number: None,
             attributes: Some(payload.clone().get_attrbutes_hashmap()),
             tags: Some(payload.clone().get_tags_hashmap()),
             created_at: None,
@@ -133,28 +217,18 @@ pub async fn process(
     warn!("db_result: {:?}", db_result);
     match db_result {
         Ok(_) => {
-            let response = CreateQueueResponse {
-                create_queue_result: CreateQueueResult {
-                    queue_url: format!("{}/{}", &app_state.host_name, payload.queue_name),
-                },
-                reponse_metadata: HashMap::new(),
-            };
-
-            let mut writer = app_state.queues.lock().await;
-            (*writer).insert(
-                payload.queue_name.clone(),
-                crate::queue::Queue::new(&payload.queue_name.clone(), vec![]),
-            );
-
-            return match quick_xml::se::to_string(&response) {
+            let mut queue = crate::queue::Queue::new();
+            queue.set_attributes(payload.clone().get_attrbutes_hashmap());
+            (*writer).insert(payload.queue_name.clone(), queue);
+
+            match quick_xml::se::to_string(&response) {
                 Ok(resp) => HttpResponse::Ok().body(resp),
                 Err(e) => HttpResponse::InternalServerError()
                     .body(format!("Failed to serialize response: {}", e)),
-            };
+            }
         }
         Err(e) => {
-            return HttpResponse::InternalServerError()
-                .body(format!("Failed to create queue: {}", e))
+            HttpResponse::InternalServerError().body(format!("Failed to create queue: {}", e))
         }
     }
 }
@@ -205,4 +279,126 @@ mod tests {
 
         assert!(params.create_validate_attributes().is_err());
     }
+
+    fn params(queue_name: &str, attributes: &[(&str, &str)]) -> CreateQueueParams {
+        let mut extra = HashMap::new();
+        for (i, (name, value)) in attributes.iter().enumerate() {
+            extra.insert(format!("Attribute.{}.Name", i + 1), name.to_string());
+            extra.insert(format!("Attribute.{}.Value", i + 1), value.to_string());
+        }
+
+        CreateQueueParams {
+            queue_name: queue_name.to_string(),
+            extra,
+            attributes: None,
+            tags: None,
+        }
+    }
+
+    #[test]
+    fn test_create_validate_attributes_fifo() {
+        let mut fifo = params(
+            "orders.fifo",
+            &[("FifoQueue", "true"), ("ContentBasedDeduplication", "true")],
+        );
+        assert!(fifo.create_validate_attributes().is_ok());
+        assert!(fifo.is_fifo());
+
+        let mut standard = params("orders", &[("FifoQueue", "false")]);
+        assert!(standard.create_validate_attributes().is_ok());
+        assert!(!standard.is_fifo());
+
+        let err = params("orders", &[("ContentBasedDeduplication", "true")])
+            .create_validate_attributes()
+            .unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeName");
+
+        let mut high_throughput = params(
+            "orders.fifo",
+            &[
+                ("FifoQueue", "true"),
+                ("DeduplicationScope", "messageGroup"),
+                ("FifoThroughputLimit", "perMessageGroupId"),
+            ],
+        );
+        assert!(high_throughput.create_validate_attributes().is_ok());
+        let err = params(
+            "orders.fifo",
+            &[
+                ("FifoQueue", "true"),
+                ("FifoThroughputLimit", "perMessageGroupId"),
+            ],
+        )
+        .create_validate_attributes()
+        .unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeValue");
+
+        let err = params("orders.fifo", &[])
+            .create_validate_attributes()
+            .unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+        let err = params("orders", &[("FifoQueue", "true")])
+            .create_validate_attributes()
+            .unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+    }
+
+    #[test]
+    fn test_validate_queue_name() {
+        assert!(validate_queue_name("my-queue_1", false).is_ok());
+        assert!(validate_queue_name(&"a".repeat(80), false).is_ok());
+        assert!(validate_queue_name(&"a".repeat(81), false).is_err());
+        assert!(validate_queue_name(&format!("{}.fifo", "a".repeat(75)), true).is_ok());
+        assert!(validate_queue_name(&format!("{}.fifo", "a".repeat(76)), true).is_err());
+        assert!(validate_queue_name("my.queue", false).is_err());
+        assert!(validate_queue_name("", false).is_err());
+        assert!(validate_queue_name(".fifo", true).is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_create_existing_queue() {
+        let mut queue = crate::queue::Queue::new();
+        queue.set_attributes(HashMap::from([
+            ("FifoQueue".to_string(), "true".to_string()),
+            ("VisibilityTimeout".to_string(), "60".to_string()),
+        ]));
+        queue.set_sequence_number(42);
+        queue.push(crate::queue::Message::new("id", "message_body"));
+        // The database has no tables, creating the queue again must not touch it
+        let app_state = Arc::new(AppState {
+            db_pool: sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap(),
+            host_name: "http://localhost:9090".to_string(),
+            enforce_quotas: false,
+            queues: Arc::new(tokio::sync::Mutex::new(HashMap::from([(
+                "orders.fifo".to_string(),
+                queue,
+            )]))),
+        });
+
+        let create = |attributes: &str| {
+            let payload = web::Bytes::from(format!(
+                "Action=CreateQueue&QueueName=orders.fifo&Attribute.1.Name=FifoQueue&Attribute.1.Value=true{}",
+                attributes
+            ));
+            let app_state = app_state.clone();
+            async move { process(app_state, &payload, false).await }
+        };
+
+        let response = create("&Attribute.2.Name=VisibilityTimeout&Attribute.2.Value=60").await;
+        assert_eq!(response.status(), 200);
+        let response = create("&Attribute.2.Name=DeduplicationScope&Attribute.2.Value=queue").await;
+        assert_eq!(response.status(), 200);
+
+        let response = create("&Attribute.2.Name=VisibilityTimeout&Attribute.2.Value=30").await;
+        assert_eq!(response.status(), 400);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("QueueAlreadyExists"));
+
+        let mut queues = app_state.queues.lock().await;
+        let queue = queues.get_mut("orders.fifo").unwrap();
+        assert_eq!(queue.approximate_number_of_messages(), 1);
+        assert_eq!(queue.next_sequence_number(), format!("{:020}", 43));
+    }
 }
@@ -0,0 +1,68 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::{error::SqsError, helpers};
+use crate::AppState;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteMessageParams {
+    queue_url: Option<String>,
+    receipt_handle: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteMessageResponse {
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Delete a received message, the receipt handle must belong to its latest receive
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<DeleteMessageParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let mut writer = app_state.queues.lock().await;
+    let queue = match (*writer).get_mut(&queue_name) {
+        Some(queue) => queue,
+        None => return SqsError::non_existent_queue().response(),
+    };
+
+    if queue.delete_message(&payload.receipt_handle).is_err() {
+        return SqsError::receipt_handle_is_invalid(&payload.receipt_handle).response();
+    }
+
+    let response = DeleteMessageResponse {
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
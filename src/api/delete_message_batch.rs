@@ -0,0 +1,169 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+use super::{
+    batch::{self, BatchResultErrorEntry},
+    error::SqsError,
+    helpers,
+};
+use crate::AppState;
+
+const ENTRY_PREFIX: &str = "DeleteMessageBatchRequestEntry";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteMessageBatchParams {
+    queue_url: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteMessageBatchResponse {
+    delete_message_batch_result: DeleteMessageBatchResult,
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteMessageBatchResult {
+    #[serde(rename = "DeleteMessageBatchResultEntry")]
+    successful: Vec<DeleteMessageBatchResultEntry>,
+    #[serde(rename = "BatchResultErrorEntry")]
+    failed: Vec<BatchResultErrorEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteMessageBatchResultEntry {
+    id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Delete up to 10 received messages, every entry succeeds or fails on its own
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<DeleteMessageBatchParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let entries = match batch::extract_batch_entries(ENTRY_PREFIX, &payload.extra) {
+        Ok(entries) => entries,
+        Err(e) => return e.response(),
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let mut writer = app_state.queues.lock().await;
+    let queue = match (*writer).get_mut(&queue_name) {
+        Some(queue) => queue,
+        None => return SqsError::non_existent_queue().response(),
+    };
+
+    let mut result = DeleteMessageBatchResult {
+        successful: vec![],
+        failed: vec![],
+    };
+    for entry in entries {
+        let deleted = match entry.get("ReceiptHandle") {
+            Some(receipt_handle) => queue
+                .delete_message(receipt_handle)
+                .map_err(|_| SqsError::receipt_handle_is_invalid(receipt_handle)),
+            None => Err(SqsError::missing_parameter("ReceiptHandle")),
+        };
+        match deleted {
+            Ok(_) => result
+                .successful
+                .push(DeleteMessageBatchResultEntry { id: entry.id }),
+            Err(err) => result
+                .failed
+                .push(BatchResultErrorEntry::new(&entry.id, &err)),
+        }
+    }
+
+    let response = DeleteMessageBatchResponse {
+        delete_message_batch_result: result,
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_delete_message_batch() {
+        let mut queue = crate::queue::Queue::new();
+        queue.push(crate::queue::Message::new("a", "message_body"));
+        queue.push(crate::queue::Message::new("b", "message_body"));
+        let valid = queue.receive(None).unwrap().0;
+        let deleted = queue.receive(None).unwrap().0;
+        queue.delete_message(&deleted).unwrap();
+        let app_state = Arc::new(AppState {
+            db_pool: sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap(),
+            host_name: "http://localhost:9090".to_string(),
+            enforce_quotas: false,
+            queues: Arc::new(tokio::sync::Mutex::new(HashMap::from([(
+                "test".to_string(),
+                queue,
+            )]))),
+        });
+
+        let payload = web::Bytes::from(
+            serde_urlencoded::to_string([
+                ("Action", "DeleteMessageBatch"),
+                ("QueueUrl", "http://localhost:9090/test"),
+                ("DeleteMessageBatchRequestEntry.1.Id", "valid"),
+                ("DeleteMessageBatchRequestEntry.1.ReceiptHandle", &valid),
+                ("DeleteMessageBatchRequestEntry.2.Id", "deleted"),
+                ("DeleteMessageBatchRequestEntry.2.ReceiptHandle", &deleted),
+                ("DeleteMessageBatchRequestEntry.3.Id", "missing"),
+            ])
+            .unwrap(),
+        );
+        let response = process(app_state.clone(), &payload, "/", false).await;
+        assert_eq!(response.status(), 200);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("<DeleteMessageBatchResultEntry><Id>valid</Id>"));
+        assert!(body.contains(
+            "<Id>deleted</Id><SenderFault>true</SenderFault><Code>ReceiptHandleIsInvalid</Code>"
+        ));
+        assert!(body.contains(
+            "<Id>missing</Id><SenderFault>true</SenderFault><Code>MissingParameter</Code>"
+        ));
+
+        let queues = app_state.queues.lock().await;
+        let queue = queues.get("test").unwrap();
+        assert_eq!(queue.approximate_number_of_messages(), 0);
+        assert_eq!(queue.approximate_number_of_messages_not_visible(), 0);
+    }
+}
@@ -0,0 +1,137 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use super::{error::SqsError, helpers};
+use crate::AppState;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteQueueParams {
+    queue_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DeleteQueueResponse {
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Delete a queue from the database and drop its messages
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<DeleteQueueParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    // Hold the lock while the rows are deleted so no message can be sent in between
+    let mut writer = app_state.queues.lock().await;
+    let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+    let deleted = match service.delete_queue(&queue_name).await {
+        Ok(deleted) => deleted,
+        Err(e) => {
+            error!("Failed to delete queue: {}", e);
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to delete queue: {}", e));
+        }
+    };
+
+    if (*writer).remove(&queue_name).is_none() && !deleted {
+        return SqsError::non_existent_queue().response();
+    }
+
+    let response = DeleteQueueResponse {
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{create_queue, get_queue_url, send_message, tests::app_state_with_database};
+
+    async fn count_rows(app_state: &AppState, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM `{}`", table))
+            .fetch_one(&app_state.db_pool)
+            .await
+            .unwrap()
+    }
+
+    async fn body(response: HttpResponse) -> String {
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        String::from_utf8_lossy(&body).to_string()
+    }
+
+    #[actix_web::test]
+    async fn test_delete_queue() {
+        let app_state = app_state_with_database().await;
+        let create = web::Bytes::from(
+            "Action=CreateQueue&QueueName=test&Attribute.1.Name=DelaySeconds&Attribute.1.Value=5&Tag.1.Key=team&Tag.1.Value=core",
+        );
+        let response = create_queue::process(app_state.clone(), &create, false).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(count_rows(&app_state, "queues").await, 1);
+        assert_eq!(count_rows(&app_state, "attributes").await, 1);
+        assert_eq!(count_rows(&app_state, "tags").await, 1);
+
+        let delete = web::Bytes::from("Action=DeleteQueue&QueueUrl=http://localhost:9090/test");
+        let response = process(app_state.clone(), &delete, "/", false).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(count_rows(&app_state, "queues").await, 0);
+        assert_eq!(count_rows(&app_state, "attributes").await, 0);
+        assert_eq!(count_rows(&app_state, "tags").await, 0);
+        assert!(!app_state.queues.lock().await.contains_key("test"));
+
+        let response = process(app_state.clone(), &delete, "/", false).await;
+        assert_eq!(response.status(), 400);
+        assert!(body(response)
+            .await
+            .contains("AWS.SimpleQueueService.NonExistentQueue"));
+
+        let send = web::Bytes::from(
+            "Action=SendMessage&QueueUrl=http://localhost:9090/test&MessageBody=message_body",
+        );
+        let response = send_message::process(app_state.clone(), &send, "/", false).await;
+        assert_eq!(response.status(), 400);
+        assert!(body(response)
+            .await
+            .contains("AWS.SimpleQueueService.NonExistentQueue"));
+
+        let get_url = web::Bytes::from("Action=GetQueueUrl&QueueName=test");
+        let response = get_queue_url::process(app_state.clone(), &get_url, false).await;
+        assert_eq!(response.status(), 400);
+        assert!(body(response)
+            .await
+            .contains("AWS.SimpleQueueService.NonExistentQueue"));
+    }
+}
@@ -0,0 +1,242 @@
+use actix_web::{http::StatusCode, HttpResponse};
+use serde::Serialize;
+
+use super::helpers;
+use crate::queue::ReceiptError;
+
+/// Error document returned by the SQS Query API
+/// https://docs.aws.amazon.com/AWSSimpleQueueService/latest/SQSDeveloperGuide/sqs-api-responses.html
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ErrorResponse {
+    error: ErrorDetail,
+    request_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ErrorDetail {
+    #[serde(rename = "Type")]
+    error_type: String,
+    code: String,
+    message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqsError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl SqsError {
+    pub fn new(status: StatusCode, code: &'static str, message: &str) -> Self {
+        SqsError {
+            status,
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn receipt_handle_is_invalid(receipt_handle: &str) -> Self {
+        SqsError::new(
+            StatusCode::NOT_FOUND,
+            "ReceiptHandleIsInvalid",
+            &format!(
+                "The input receipt handle \"{}\" is not a valid receipt handle.",
+                receipt_handle
+            ),
+        )
+    }
+
+    pub fn message_not_inflight() -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "AWS.SimpleQueueService.MessageNotInflight",
+            "The message referred to isn't in flight.",
+        )
+    }
+
+    pub fn from_receipt_error(err: ReceiptError, receipt_handle: &str) -> Self {
+        match err {
+            ReceiptError::Invalid => SqsError::receipt_handle_is_invalid(receipt_handle),
+            ReceiptError::NotInFlight => SqsError::message_not_inflight(),
+        }
+    }
+
+    pub fn request_throttled() -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "RequestThrottled",
+            "Exceeds the permitted request rate for the queue or for the recipient of the request.",
+        )
+    }
+
+    pub fn invalid_parameter_value(message: &str) -> Self {
+        SqsError::new(StatusCode::BAD_REQUEST, "InvalidParameterValue", message)
+    }
+
+    pub fn invalid_attribute_name(name: &str) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidAttributeName",
+            &format!("Unknown Attribute {}.", name),
+        )
+    }
+
+    pub fn invalid_attribute_value(name: &str, reason: &str) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidAttributeValue",
+            &format!(
+                "Invalid value for the parameter {}. Reason: {}",
+                name, reason
+            ),
+        )
+    }
+
+    pub fn purge_queue_in_progress(queue_name: &str) -> Self {
+        SqsError::new(
+            StatusCode::FORBIDDEN,
+            "AWS.SimpleQueueService.PurgeQueueInProgress",
+            &format!(
+                "Only one PurgeQueue operation on {} is allowed every 60 seconds.",
+                queue_name
+            ),
+        )
+    }
+
+    pub fn non_existent_queue() -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "AWS.SimpleQueueService.NonExistentQueue",
+            "The specified queue does not exist.",
+        )
+    }
+
+    pub fn queue_already_exists(name: &str) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "QueueAlreadyExists",
+            &format!(
+                "A queue already exists with the same name and a different value for attribute {}",
+                name
+            ),
+        )
+    }
+
+    pub fn missing_parameter(name: &str) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "MissingParameter",
+            &format!("The request must contain the parameter {}.", name),
+        )
+    }
+
+    pub fn invalid_message_contents(character: char) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidMessageContents",
+            &format!(
+                "Invalid binary character '#x{:X}' was found in the message body, the set of allowed characters is #x9 | #xA | #xD | #x20 to #xD7FF | #xE000 to #xFFFD | #x10000 to #x10FFFF",
+                character as u32
+            ),
+        )
+    }
+
+    pub fn batch_request_too_long(max: usize) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "AWS.SimpleQueueService.BatchRequestTooLong",
+            &format!("Batch requests cannot be longer than {} bytes.", max),
+        )
+    }
+
+    pub fn empty_batch_request() -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "AWS.SimpleQueueService.EmptyBatchRequest",
+            "There should be at least one batch request entry.",
+        )
+    }
+
+    pub fn too_many_entries_in_batch_request(max: usize) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "AWS.SimpleQueueService.TooManyEntriesInBatchRequest",
+            &format!("Maximum number of entries per request are {}.", max),
+        )
+    }
+
+    pub fn batch_entry_ids_not_distinct(id: &str) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "AWS.SimpleQueueService.BatchEntryIdsNotDistinct",
+            &format!("Id {} repeated.", id),
+        )
+    }
+
+    pub fn invalid_batch_entry_id(id: &str) -> Self {
+        SqsError::new(
+            StatusCode::BAD_REQUEST,
+            "AWS.SimpleQueueService.InvalidBatchEntryId",
+            &format!(
+                "A batch entry id can only contain alphanumeric characters, hyphens and underscores. It can be at most 80 letters long. Invalid id: {}",
+                id
+            ),
+        )
+    }
+
+    fn error_type(&self) -> &'static str {
+        if self.is_sender_fault() {
+            "Sender"
+        } else {
+            "Receiver"
+        }
+    }
+
+    /// Sender faults are reported with 4xx status codes, everything else is on the server
+    pub fn is_sender_fault(&self) -> bool {
+        self.status.is_client_error()
+    }
+
+    pub fn response(&self) -> HttpResponse {
+        let response = ErrorResponse {
+            error: ErrorDetail {
+                error_type: self.error_type().to_string(),
+                code: self.code.to_string(),
+                message: self.message.clone(),
+            },
+            request_id: helpers::generate_random_uuid4(),
+        };
+
+        match quick_xml::se::to_string(&response) {
+            Ok(resp) => HttpResponse::build(self.status).body(resp),
+            Err(e) => HttpResponse::InternalServerError()
+                .body(format!("Failed to serialize response: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_error_response_document() {
+        let resp = SqsError::non_existent_queue().response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.starts_with("<ErrorResponse><Error><Type>Sender</Type>"));
+        assert!(body.contains("<Code>AWS.SimpleQueueService.NonExistentQueue</Code>"));
+    }
+
+    #[test]
+    fn test_error_type() {
+        assert_eq!(SqsError::non_existent_queue().error_type(), "Sender");
+        let err = SqsError::new(StatusCode::INTERNAL_SERVER_ERROR, "InternalFailure", "boom");
+        assert_eq!(err.error_type(), "Receiver");
+    }
+}
@@ -0,0 +1,198 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tracing::error;
+
+use super::{
+    create_queue::{ATTR_FIFO, ATTR_LIST},
+    error::SqsError,
+    helpers,
+};
+use crate::{
+    queue::{DEFAULT_ATTRIBUTES, DEFAULT_FIFO_ATTRIBUTES},
+    AppState,
+};
+
+/// Attributes which are computed from the queue instead of being stored
+const ATTR_COMPUTED: [&str; 6] = [
+    "ApproximateNumberOfMessages",
+    "ApproximateNumberOfMessagesNotVisible",
+    "ApproximateNumberOfMessagesDelayed",
+    "CreatedTimestamp",
+    "LastModifiedTimestamp",
+    "QueueArn",
+];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetQueueAttributesParams {
+    queue_url: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetQueueAttributesResponse {
+    get_queue_attributes_result: GetQueueAttributesResult,
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetQueueAttributesResult {
+    #[serde(rename = "Attribute")]
+    attributes: Vec<helpers::ParamValues>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Check the requested attribute names, `All` selects every attribute
+fn requested_attributes(names: Vec<String>) -> Result<Vec<String>, SqsError> {
+    for name in &names {
+        if name != "All"
+            && !ATTR_LIST.contains(&name.as_str())
+            && !ATTR_FIFO.contains(&name.as_str())
+            && !ATTR_COMPUTED.contains(&name.as_str())
+        {
+            return Err(SqsError::invalid_attribute_name(name));
+        }
+    }
+
+    Ok(names)
+}
+
+/// Return the attributes of a queue, stored values fall back to the defaults and live values
+/// come from the in-memory queue
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<GetQueueAttributesParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let requested = match requested_attributes(helpers::extract_indexed_values(
+        "AttributeName",
+        &payload.extra,
+    )) {
+        Ok(requested) => requested,
+        Err(e) => return e.response(),
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+    let queue = match service.get_queue(&queue_name).await {
+        Ok(Some(queue)) => queue,
+        Ok(None) => return SqsError::non_existent_queue().response(),
+        Err(e) => {
+            error!("Failed to get queue: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to get queue: {}", e));
+        }
+    };
+    let stored = match service.get_attributes(queue.id.unwrap_or_default()).await {
+        Ok(stored) => stored,
+        Err(e) => {
+            error!("Failed to get queue attributes: {}", e);
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to get queue attributes: {}", e));
+        }
+    };
+
+    let mut values: Vec<(String, String)> = DEFAULT_ATTRIBUTES
+        .iter()
+        .chain(DEFAULT_FIFO_ATTRIBUTES.iter().filter(|_| queue.is_fifo()))
+        .filter(|(name, _)| !stored.contains_key(*name))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    values.extend(stored);
+
+    {
+        let reader = app_state.queues.lock().await;
+        let (visible, not_visible, delayed) = match (*reader).get(&queue_name) {
+            Some(queue) => (
+                queue.approximate_number_of_messages(),
+                queue.approximate_number_of_messages_not_visible(),
+                queue.approximate_number_of_messages_delayed(),
+            ),
+            None => (0, 0, 0),
+        };
+        values.push((
+            "ApproximateNumberOfMessages".to_string(),
+            visible.to_string(),
+        ));
+        values.push((
+            "ApproximateNumberOfMessagesNotVisible".to_string(),
+            not_visible.to_string(),
+        ));
+        values.push((
+            "ApproximateNumberOfMessagesDelayed".to_string(),
+            delayed.to_string(),
+        ));
+    }
+
+    let timestamp = |at: Option<time::OffsetDateTime>| {
+        at.map(|at| at.unix_timestamp().to_string())
+            .unwrap_or_default()
+    };
+    values.push(("CreatedTimestamp".to_string(), timestamp(queue.created_at)));
+    values.push((
+        "LastModifiedTimestamp".to_string(),
+        timestamp(queue.updated_at),
+    ));
+    values.push(("QueueArn".to_string(), service.queue_arn(&queue.name)));
+
+    let all = requested.iter().any(|name| name == "All");
+    values.retain(|(name, _)| all || requested.contains(name));
+    values.sort();
+
+    let response = GetQueueAttributesResponse {
+        get_queue_attributes_result: GetQueueAttributesResult {
+            attributes: values
+                .into_iter()
+                .map(|(name, value)| helpers::ParamValues { name, value })
+                .collect(),
+        },
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_attributes() {
+        let names = vec![
+            "All".to_string(),
+            "QueueArn".to_string(),
+            "FifoThroughputLimit".to_string(),
+        ];
+        assert_eq!(requested_attributes(names.clone()), Ok(names));
+
+        let err = requested_attributes(vec!["NOT_EXISTS".to_string()]).unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeName");
+    }
+}
@@ -0,0 +1,103 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use super::{error::SqsError, helpers};
+use crate::{service::queue::ACCOUNT_ID, AppState};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetQueueUrlParams {
+    queue_name: String,
+    #[serde(rename = "QueueOwnerAWSAccountId")]
+    queue_owner_aws_account_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetQueueUrlResponse {
+    get_queue_url_result: GetQueueUrlResult,
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetQueueUrlResult {
+    queue_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Check the owner account of a lookup, only queues of this server's account exist
+fn validate_owner_account_id(account_id: Option<&str>) -> Result<(), SqsError> {
+    match account_id {
+        Some(account_id) if !account_id.is_empty() && account_id != ACCOUNT_ID => {
+            Err(SqsError::non_existent_queue())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Resolve the URL of a queue by its name
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<GetQueueUrlParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    if let Err(e) = validate_owner_account_id(payload.queue_owner_aws_account_id.as_deref()) {
+        return e.response();
+    }
+
+    let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+    let queue = match service.get_queue(&payload.queue_name).await {
+        Ok(Some(queue)) => queue,
+        Ok(None) => return SqsError::non_existent_queue().response(),
+        Err(e) => {
+            error!("Failed to get queue: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to get queue: {}", e));
+        }
+    };
+
+    let response = GetQueueUrlResponse {
+        get_queue_url_result: GetQueueUrlResult {
+            queue_url: service.queue_url(&queue.name, None),
+        },
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_owner_account_id() {
+        assert_eq!(validate_owner_account_id(None), Ok(()));
+        assert_eq!(validate_owner_account_id(Some("")), Ok(()));
+        assert_eq!(validate_owner_account_id(Some(ACCOUNT_ID)), Ok(()));
+
+        let err = validate_owner_account_id(Some("123456789012")).unwrap_err();
+        assert_eq!(err.code, "AWS.SimpleQueueService.NonExistentQueue");
+    }
+}
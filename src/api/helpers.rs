@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::queue::MessageAttribute;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -10,10 +13,7 @@ pub struct ParamValues {
     pub value: String,
 }
 
-pub fn extract_from_extra<'a>(
-    re: Regex,
-    extra: HashMap<String, String>,
-) -> Option<Vec<ParamValues>> {
+pub fn extract_from_extra(re: Regex, extra: HashMap<String, String>) -> Option<Vec<ParamValues>> {
     let mut attrs: Vec<ParamValues> = Vec::new();
     for _i in 0..extra.len() {
         attrs.push(ParamValues {
@@ -36,11 +36,57 @@ pub fn extract_from_extra<'a>(
     }
 
     // Cleanup empty attributes
-    attrs.retain(|attr| attr.name != "");
+    attrs.retain(|attr| !attr.name.is_empty());
 
     Some(attrs)
 }
 
+/// Group the `<prefix>.N.<field>` parameters by N, ordered by the index
+pub fn extract_entries(
+    prefix: &str,
+    extra: &HashMap<String, String>,
+) -> Vec<HashMap<String, String>> {
+    let re = RegexBuilder::new(&format!(r"^{}\.(\d+)\.(.+)$", regex::escape(prefix)))
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+
+    let mut entries: BTreeMap<usize, HashMap<String, String>> = BTreeMap::new();
+    for (key, value) in extra.iter() {
+        if let Some(caps) = re.captures(key) {
+            let index = match caps.get(1).unwrap().as_str().parse::<usize>() {
+                Ok(index) => index,
+                Err(_) => continue,
+            };
+            entries
+                .entry(index)
+                .or_default()
+                .insert(caps.get(2).unwrap().as_str().to_string(), value.to_string());
+        }
+    }
+
+    entries.into_values().collect()
+}
+
+/// Collect the values of the `<prefix>.N` parameters, ordered by N
+pub fn extract_indexed_values(prefix: &str, extra: &HashMap<String, String>) -> Vec<String> {
+    let re = RegexBuilder::new(&format!(r"^{}\.(\d+)$", regex::escape(prefix)))
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+
+    let mut values: BTreeMap<usize, String> = BTreeMap::new();
+    for (key, value) in extra.iter() {
+        if let Some(caps) = re.captures(key) {
+            if let Ok(index) = caps.get(1).unwrap().as_str().parse::<usize>() {
+                values.insert(index, value.to_string());
+            }
+        }
+    }
+
+    values.into_values().collect()
+}
+
 pub fn get_attrbutes_hashmap(attributes: Option<Vec<ParamValues>>) -> HashMap<String, String> {
     let mut map = HashMap::new();
     if let Some(attrs) = attributes {
@@ -49,7 +95,7 @@ pub fn get_attrbutes_hashmap(attributes: Option<Vec<ParamValues>>) -> HashMap<St
         }
     }
 
-    return map;
+    map
 }
 
 pub fn generate_random_uuid4() -> String {
@@ -62,6 +108,68 @@ pub fn compute_md5(input: &str) -> String {
     format!("{:x}", digest)
 }
 
+/// Hex encoded SHA-256, used as the deduplication id of content based deduplication
+pub fn compute_sha256(input: &str) -> String {
+    Sha256::digest(input.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// MD5 of message attributes the way the AWS SDKs verify it. For every attribute, sorted
+/// by name, the name, data type and value are each prefixed with their length as a 4 byte
+/// big endian integer, and the value with a transport type byte, 1 for String and Number,
+/// 2 for Binary.
+pub fn compute_md5_of_message_attributes<'a>(
+    attributes: impl IntoIterator<Item = (&'a String, &'a MessageAttribute)>,
+) -> String {
+    let mut attributes: Vec<(&String, &MessageAttribute)> = attributes.into_iter().collect();
+    attributes.sort_by_key(|(name, _)| *name);
+
+    let mut buffer: Vec<u8> = Vec::new();
+    let push_with_length = |buffer: &mut Vec<u8>, bytes: &[u8]| {
+        buffer.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        buffer.extend_from_slice(bytes);
+    };
+    for (name, attribute) in attributes {
+        push_with_length(&mut buffer, name.as_bytes());
+        push_with_length(&mut buffer, attribute.data_type.as_bytes());
+        match (&attribute.string_value, &attribute.binary_value) {
+            (_, Some(binary_value)) => {
+                buffer.push(2);
+                push_with_length(&mut buffer, binary_value);
+            }
+            (string_value, None) => {
+                buffer.push(1);
+                push_with_length(
+                    &mut buffer,
+                    string_value.as_deref().unwrap_or_default().as_bytes(),
+                );
+            }
+        }
+    }
+
+    format!("{:x}", md5::compute(buffer))
+}
+
+/// Get the queue name from a queue URL or the path of one.
+/// Supported are `/<name>`, the legacy `/queue/<name>` and `/<account id>/<name>`.
+pub fn queue_name_from_url(queue_url: &str) -> Option<String> {
+    let path = match queue_url.split_once("://") {
+        Some((_scheme, rest)) => rest.split_once('/').map_or("", |(_host, path)| path),
+        None => queue_url,
+    };
+
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    match segments.as_slice() {
+        [name] | [_, name] => Some(name.to_string()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use regex::RegexBuilder;
@@ -90,4 +198,103 @@ mod tests {
         assert_eq!(attrs[1].name, "MaximumMessageSize");
         assert_eq!(attrs[1].value, "262144");
     }
+
+    #[test]
+    fn test_extract_entries() {
+        let mut extra = HashMap::new();
+        extra.insert("Entry.2.Id".to_string(), "second".to_string());
+        extra.insert("Entry.1.Id".to_string(), "first".to_string());
+        extra.insert("Entry.1.ReceiptHandle".to_string(), "handle".to_string());
+        extra.insert(
+            "QueueUrl".to_string(),
+            "http://localhost:9090/myqueue".to_string(),
+        );
+
+        let entries = extract_entries("Entry", &extra);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].get("Id"), Some(&"first".to_string()));
+        assert_eq!(entries[0].get("ReceiptHandle"), Some(&"handle".to_string()));
+        assert_eq!(entries[1].get("Id"), Some(&"second".to_string()));
+    }
+
+    #[test]
+    fn test_extract_indexed_values() {
+        let mut extra = HashMap::new();
+        extra.insert(
+            "AttributeName.2".to_string(),
+            "VisibilityTimeout".to_string(),
+        );
+        extra.insert("AttributeName.1".to_string(), "DelaySeconds".to_string());
+        extra.insert("AttributeName.1.Name".to_string(), "Ignored".to_string());
+
+        let values = extract_indexed_values("AttributeName", &extra);
+        assert_eq!(
+            values,
+            vec!["DelaySeconds".to_string(), "VisibilityTimeout".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_queue_name_from_url() {
+        assert_eq!(
+            queue_name_from_url("http://localhost:9090/queue/myqueue"),
+            Some("myqueue".to_string())
+        );
+        assert_eq!(
+            queue_name_from_url("http://localhost:9090/myqueue/"),
+            Some("myqueue".to_string())
+        );
+        assert_eq!(
+            queue_name_from_url("http://localhost:9090/000000000000/myqueue"),
+            Some("myqueue".to_string())
+        );
+        assert_eq!(
+            queue_name_from_url("/queue/myqueue"),
+            Some("myqueue".to_string())
+        );
+        assert_eq!(queue_name_from_url("http://localhost:9090"), None);
+        assert_eq!(
+            queue_name_from_url("http://localhost:9090/a/b/myqueue"),
+            None
+        );
+        assert_eq!(queue_name_from_url(""), None);
+    }
+
+    #[test]
+    fn test_compute_sha256() {
+        assert_eq!(
+            compute_sha256("hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_compute_md5_of_message_attributes() {
+        let string = |value: &str| MessageAttribute {
+            data_type: "String".to_string(),
+            string_value: Some(value.to_string()),
+            binary_value: None,
+        };
+        let mut attributes = BTreeMap::new();
+        attributes.insert("test".to_string(), string("test"));
+        assert_eq!(
+            compute_md5_of_message_attributes(&attributes),
+            "ddb45ae313fa7f1b0fbf07d6f3b9e1c5"
+        );
+
+        let mut attributes = BTreeMap::new();
+        attributes.insert("city".to_string(), string("Tehran"));
+        attributes.insert(
+            "blob".to_string(),
+            MessageAttribute {
+                data_type: "Binary".to_string(),
+                string_value: None,
+                binary_value: Some(vec![1, 2, 3]),
+            },
+        );
+        assert_eq!(
+            compute_md5_of_message_attributes(&attributes),
+            "a8d7740810bb4d1d43bb7bde34849b58"
+        );
+    }
 }
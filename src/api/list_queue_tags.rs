@@ -0,0 +1,97 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+
+use super::{error::SqsError, helpers};
+use crate::AppState;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListQueueTagsParams {
+    queue_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListQueueTagsResponse {
+    list_queue_tags_result: ListQueueTagsResult,
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListQueueTagsResult {
+    #[serde(rename = "Tag")]
+    tags: Vec<Tag>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Tag {
+    key: String,
+    value: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// List all tags of a queue
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<ListQueueTagsParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+    let queue = match service.get_queue(&queue_name).await {
+        Ok(Some(queue)) => queue,
+        Ok(None) => return SqsError::non_existent_queue().response(),
+        Err(e) => {
+            error!("Failed to get queue: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to get queue: {}", e));
+        }
+    };
+
+    let mut tags: Vec<Tag> = match service.get_tags(queue.id.unwrap_or_default()).await {
+        Ok(tags) => tags
+            .into_iter()
+            .map(|(key, value)| Tag { key, value })
+            .collect(),
+        Err(e) => {
+            error!("Failed to get queue tags: {}", e);
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to get queue tags: {}", e));
+        }
+    };
+    tags.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let response = ListQueueTagsResponse {
+        list_queue_tags_result: ListQueueTagsResult { tags },
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{error::SqsError, helpers};
+use crate::queue::MessageAttribute;
+
+/// A message can carry at most 10 attributes
+pub const MAX_MESSAGE_ATTRIBUTES: usize = 10;
+
+const MAX_NAME_LENGTH: usize = 256;
+
+/// One `<MessageAttribute>` of a received message
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessageAttributeEntry {
+    name: String,
+    value: MessageAttributeValue,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct MessageAttributeValue {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    string_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    binary_value: Option<String>,
+    data_type: String,
+}
+
+impl MessageAttributeEntry {
+    pub fn new(name: &str, attribute: &MessageAttribute) -> Self {
+        MessageAttributeEntry {
+            name: name.to_string(),
+            value: MessageAttributeValue {
+                string_value: attribute.string_value.clone(),
+                binary_value: attribute
+                    .binary_value
+                    .as_ref()
+                    .map(|value| STANDARD.encode(value)),
+                data_type: attribute.data_type.clone(),
+            },
+        }
+    }
+}
+
+fn invalid(message: &str) -> SqsError {
+    SqsError::invalid_parameter_value(message)
+}
+
+fn validate_name(name: &str) -> Result<(), SqsError> {
+    if name.is_empty() {
+        return Err(invalid(
+            "The request must contain non-empty message attribute name.",
+        ));
+    }
+
+    let lowercase = name.to_lowercase();
+    let is_valid = name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+        && !name.starts_with('.')
+        && !name.ends_with('.')
+        && !name.contains("..")
+        && !lowercase.starts_with("aws.")
+        && !lowercase.starts_with("amazon.");
+    if !is_valid {
+        return Err(invalid(&format!(
+            "Message attribute name '{}' is invalid. Reason: Names can contain alphanumeric characters, hyphens, underscores and periods, must not start or end with a period or contain two periods in a row, and must not start with AWS. or Amazon.",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+/// Read the typed value of one `MessageAttribute.N` entry
+fn parse_value(name: &str, entry: &HashMap<String, String>) -> Result<MessageAttribute, SqsError> {
+    let data_type = match entry.get("Value.DataType") {
+        Some(data_type) if !data_type.is_empty() => data_type.clone(),
+        _ => {
+            return Err(invalid(&format!(
+                "The message attribute '{}' must contain non-empty message attribute type.",
+                name
+            )))
+        }
+    };
+    if data_type.len() > MAX_NAME_LENGTH {
+        return Err(invalid(&format!(
+            "Message attribute '{}' has a type longer than {} characters.",
+            name, MAX_NAME_LENGTH
+        )));
+    }
+
+    let base_type = data_type
+        .split_once('.')
+        .map_or(data_type.as_str(), |(base, _)| base);
+    let missing_value = || {
+        invalid(&format!(
+            "The message attribute '{}' must contain non-empty message attribute value for message attribute type '{}'.",
+            name, data_type
+        ))
+    };
+    match base_type {
+        "String" | "Number" => {
+            let value = match entry.get("Value.StringValue") {
+                Some(value) if !value.is_empty() => value.clone(),
+                _ => return Err(missing_value()),
+            };
+            if base_type == "Number" && value.trim().parse::<f64>().is_err() {
+                return Err(invalid(&format!(
+                    "Can't cast the value of message (user) attribute '{}' to a number.",
+                    name
+                )));
+            }
+            Ok(MessageAttribute {
+                data_type: data_type.clone(),
+                string_value: Some(value),
+                binary_value: None,
+            })
+        }
+        "Binary" => {
+            let value = match entry.get("Value.BinaryValue") {
+                Some(value) if !value.is_empty() => value,
+                _ => return Err(missing_value()),
+            };
+            let value = STANDARD.decode(value).map_err(|_| {
+                invalid(&format!(
+                    "The message attribute '{}' has an invalid base64 encoded binary value.",
+                    name
+                ))
+            })?;
+            Ok(MessageAttribute {
+                data_type: data_type.clone(),
+                string_value: None,
+                binary_value: Some(value),
+            })
+        }
+        _ => Err(invalid(&format!(
+            "The message attribute '{}' has an invalid message attribute type, the set of supported type prefixes is Binary, Number, and String.",
+            name
+        ))),
+    }
+}
+
+/// Read the `MessageAttribute.N.*` parameters of a send into typed attributes keyed by name
+pub fn parse(
+    fields: &HashMap<String, String>,
+) -> Result<BTreeMap<String, MessageAttribute>, SqsError> {
+    let entries = helpers::extract_entries("MessageAttribute", fields);
+    if entries.len() > MAX_MESSAGE_ATTRIBUTES {
+        return Err(invalid(&format!(
+            "Number of message attributes [{}] exceeds the allowed maximum [{}].",
+            entries.len(),
+            MAX_MESSAGE_ATTRIBUTES
+        )));
+    }
+
+    let mut attributes = BTreeMap::new();
+    for entry in entries {
+        let name = entry.get("Name").cloned().unwrap_or_default();
+        validate_name(&name)?;
+        if attributes.contains_key(&name) {
+            return Err(invalid(&format!(
+                "Message attribute name '{}' already exists.",
+                name
+            )));
+        }
+
+        let attribute = parse_value(&name, &entry)?;
+        attributes.insert(name, attribute);
+    }
+
+    Ok(attributes)
+}
+
+/// Read the `MessageSystemAttribute.N.*` parameters of a send, only `AWSTraceHeader` exists
+pub fn parse_system(
+    fields: &HashMap<String, String>,
+) -> Result<BTreeMap<String, MessageAttribute>, SqsError> {
+    let mut attributes = BTreeMap::new();
+    for entry in helpers::extract_entries("MessageSystemAttribute", fields) {
+        let name = entry.get("Name").cloned().unwrap_or_default();
+        if name != "AWSTraceHeader" || attributes.contains_key(&name) {
+            return Err(invalid(&format!(
+                "Message system attribute name '{}' is invalid.",
+                name
+            )));
+        }
+
+        let attribute = parse_value(&name, &entry)?;
+        if attribute.data_type != "String" {
+            return Err(invalid(&format!(
+                "Message system attribute '{}' must be of type String.",
+                name
+            )));
+        }
+        attributes.insert(name, attribute);
+    }
+
+    Ok(attributes)
+}
+
+/// Bytes the attributes add to the message size, each name, data type and value with
+/// binary values counted decoded
+pub fn size(attributes: &BTreeMap<String, MessageAttribute>) -> usize {
+    attributes
+        .iter()
+        .map(|(name, attribute)| {
+            name.len()
+                + attribute.data_type.len()
+                + attribute
+                    .string_value
+                    .as_ref()
+                    .map_or(0, |value| value.len())
+                + attribute
+                    .binary_value
+                    .as_ref()
+                    .map_or(0, |value| value.len())
+        })
+        .sum()
+}
+
+/// Whether an attribute is selected by the names of a receive, which can be
+/// exact names, `All`, `.*` or a `prefix.*` wildcard
+pub fn is_selected(name: &str, selected: &[String]) -> bool {
+    selected.iter().any(|pattern| match pattern.as_str() {
+        "All" | ".*" => true,
+        _ => match pattern.strip_suffix(".*") {
+            Some(prefix) => name
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('.')),
+            None => pattern == name,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let attributes = parse(&fields(&[
+            ("MessageAttribute.1.Name", "city"),
+            ("MessageAttribute.1.Value.DataType", "String"),
+            ("MessageAttribute.1.Value.StringValue", "Tehran"),
+            ("MessageAttribute.2.Name", "count"),
+            ("MessageAttribute.2.Value.DataType", "Number.int"),
+            ("MessageAttribute.2.Value.StringValue", "42"),
+            ("MessageAttribute.3.Name", "blob"),
+            ("MessageAttribute.3.Value.DataType", "Binary"),
+            ("MessageAttribute.3.Value.BinaryValue", "AQID"),
+        ]))
+        .unwrap();
+
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attributes["city"].string_value.as_deref(), Some("Tehran"));
+        assert_eq!(attributes["count"].data_type, "Number.int");
+        assert_eq!(attributes["blob"].binary_value, Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases: [&[(&str, &str)]; 5] = [
+            &[
+                ("MessageAttribute.1.Name", "AWS.reserved"),
+                ("MessageAttribute.1.Value.DataType", "String"),
+                ("MessageAttribute.1.Value.StringValue", "x"),
+            ],
+            &[
+                ("MessageAttribute.1.Name", "kind"),
+                ("MessageAttribute.1.Value.DataType", "Text"),
+                ("MessageAttribute.1.Value.StringValue", "x"),
+            ],
+            &[
+                ("MessageAttribute.1.Name", "count"),
+                ("MessageAttribute.1.Value.DataType", "Number"),
+                ("MessageAttribute.1.Value.StringValue", "many"),
+            ],
+            &[
+                ("MessageAttribute.1.Name", "blob"),
+                ("MessageAttribute.1.Value.DataType", "Binary"),
+            ],
+            &[
+                ("MessageAttribute.1.Name", "a..b"),
+                ("MessageAttribute.1.Value.DataType", "String"),
+                ("MessageAttribute.1.Value.StringValue", "x"),
+            ],
+        ];
+        for case in cases {
+            let err = parse(&fields(case)).unwrap_err();
+            assert_eq!(err.code, "InvalidParameterValue");
+        }
+    }
+
+    #[test]
+    fn test_parse_system() {
+        let attributes = parse_system(&fields(&[
+            ("MessageSystemAttribute.1.Name", "AWSTraceHeader"),
+            ("MessageSystemAttribute.1.Value.DataType", "String"),
+            ("MessageSystemAttribute.1.Value.StringValue", "Root=1-abc"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            attributes["AWSTraceHeader"].string_value.as_deref(),
+            Some("Root=1-abc")
+        );
+
+        let err = parse_system(&fields(&[
+            ("MessageSystemAttribute.1.Name", "SenderId"),
+            ("MessageSystemAttribute.1.Value.DataType", "String"),
+            ("MessageSystemAttribute.1.Value.StringValue", "me"),
+        ]))
+        .unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+    }
+
+    #[test]
+    fn test_size() {
+        let attributes = parse(&fields(&[
+            ("MessageAttribute.1.Name", "city"),
+            ("MessageAttribute.1.Value.DataType", "String"),
+            ("MessageAttribute.1.Value.StringValue", "Tehran"),
+            ("MessageAttribute.2.Name", "blob"),
+            ("MessageAttribute.2.Value.DataType", "Binary"),
+            ("MessageAttribute.2.Value.BinaryValue", "AQID"),
+        ]))
+        .unwrap();
+        assert_eq!(size(&attributes), (4 + 6 + 6) + (4 + 6 + 3));
+    }
+
+    #[test]
+    fn test_is_selected() {
+        let selected = vec!["city".to_string(), "order.*".to_string()];
+        assert!(is_selected("city", &selected));
+        assert!(is_selected("order.id", &selected));
+        assert!(!is_selected("orders", &selected));
+        assert!(!is_selected("country", &selected));
+        assert!(is_selected("anything", &["All".to_string()]));
+        assert!(!is_selected("anything", &[]));
+    }
+
+    #[test]
+    fn test_entry_serialization() {
+        let attribute = MessageAttribute {
+            data_type: "Binary".to_string(),
+            string_value: None,
+            binary_value: Some(vec![1, 2, 3]),
+        };
+        let xml =
+            quick_xml::se::to_string(&MessageAttributeEntry::new("blob", &attribute)).unwrap();
+        assert_eq!(
+            xml,
+            "<MessageAttributeEntry><Name>blob</Name><Value><BinaryValue>AQID</BinaryValue><DataType>Binary</DataType></Value></MessageAttributeEntry>"
+        );
+    }
+}
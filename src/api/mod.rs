@@ -1,13 +1,28 @@
 use crate::AppState;
 use actix_web::{post, web, HttpRequest, HttpResponse};
-use quick_xml::de::from_str;
 use serde::{de::DeserializeOwned, Deserialize};
 
+mod batch;
+mod change_message_visibility;
+mod change_message_visibility_batch;
 mod create_queue;
+mod delete_message;
+mod delete_message_batch;
+mod delete_queue;
+mod error;
+mod get_queue_attributes;
+mod get_queue_url;
 mod helpers;
+mod list_queue_tags;
 mod list_queues;
+mod message_attributes;
+mod purge_queue;
 mod receive_message;
 mod send_message;
+mod send_message_batch;
+mod set_queue_attributes;
+mod tag_queue;
+mod untag_queue;
 
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
@@ -15,7 +30,8 @@ struct RequestPayload {
     action: String,
 }
 
-#[post("/")]
+/// Queue scoped actions can be posted to the queue URL itself, so every path is accepted
+#[post("/{queue_path:.*}")]
 pub async fn post_handler(
     app_state: web::Data<AppState>,
     payload: web::Bytes,
@@ -31,21 +47,93 @@ pub async fn post_handler(
         return HttpResponse::BadRequest().body("JSON is not supported yet");
     }
 
-    return match action.to_lowercase().as_str() {
+    let queue_path = req.path();
+
+    match action.to_lowercase().as_str() {
         "amazonsqs.createqueue" | "createqueue" => {
             create_queue::process(app_state.into_inner(), &payload, is_json).await
         }
+        "amazonsqs.deletequeue" | "deletequeue" => {
+            delete_queue::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.getqueueattributes" | "getqueueattributes" => {
+            get_queue_attributes::process(app_state.into_inner(), &payload, queue_path, is_json)
+                .await
+        }
+        "amazonsqs.getqueueurl" | "getqueueurl" => {
+            get_queue_url::process(app_state.into_inner(), &payload, is_json).await
+        }
         "amazonsqs.listqueues" | "listqueues" => {
             list_queues::process(&app_state, &payload, is_json).await
         }
         "amazonsqs.sendmessage" | "sendmessage" => {
-            send_message::process(app_state.into_inner(), &payload, is_json).await
+            send_message::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.sendmessagebatch" | "sendmessagebatch" => {
+            send_message_batch::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.setqueueattributes" | "setqueueattributes" => {
+            set_queue_attributes::process(app_state.into_inner(), &payload, queue_path, is_json)
+                .await
         }
         "amazonsqs.receivemessage" | "receivemessage" => {
-            receive_message::process(app_state.into_inner(), &payload, is_json).await
+            receive_message::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.changemessagevisibility" | "changemessagevisibility" => {
+            change_message_visibility::process(
+                app_state.into_inner(),
+                &payload,
+                queue_path,
+                is_json,
+            )
+            .await
+        }
+        "amazonsqs.changemessagevisibilitybatch" | "changemessagevisibilitybatch" => {
+            change_message_visibility_batch::process(
+                app_state.into_inner(),
+                &payload,
+                queue_path,
+                is_json,
+            )
+            .await
+        }
+        "amazonsqs.deletemessage" | "deletemessage" => {
+            delete_message::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.deletemessagebatch" | "deletemessagebatch" => {
+            delete_message_batch::process(app_state.into_inner(), &payload, queue_path, is_json)
+                .await
+        }
+        "amazonsqs.purgequeue" | "purgequeue" => {
+            purge_queue::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.tagqueue" | "tagqueue" => {
+            tag_queue::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.untagqueue" | "untagqueue" => {
+            untag_queue::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        "amazonsqs.listqueuetags" | "listqueuetags" => {
+            list_queue_tags::process(app_state.into_inner(), &payload, queue_path, is_json).await
+        }
+        _ => HttpResponse::BadRequest().body("Invalid action"),
+    }
+}
+
+/// Name of the queue an action targets, from the `QueueUrl` parameter or the request path
+pub(crate) fn target_queue_name(
+    queue_url: Option<&str>,
+    queue_path: &str,
+) -> Result<String, error::SqsError> {
+    let queue_url = match queue_url {
+        Some(queue_url) => queue_url,
+        None if queue_path.trim_matches('/').is_empty() => {
+            return Err(error::SqsError::missing_parameter("QueueUrl"))
         }
-        _ => return HttpResponse::BadRequest().body("Invalid action"),
+        None => queue_path,
     };
+
+    helpers::queue_name_from_url(queue_url).ok_or_else(error::SqsError::non_existent_queue)
 }
 
 pub(crate) fn struct_from_url_encode<T>(payload: &web::Bytes) -> Result<T, actix_web::Error>
@@ -64,14 +152,64 @@ where
 }
 
 fn get_action_name(payload: &web::Bytes, req: &HttpRequest) -> Option<String> {
-    return match req.headers().get("x-amz-target") {
+    match req.headers().get("x-amz-target") {
         Some(target) => Some(target.to_str().unwrap().to_string()),
         None => {
-            let act = struct_from_url_encode::<RequestPayload>(&payload);
+            let act = struct_from_url_encode::<RequestPayload>(payload);
             if act.is_err() {
                 return None;
             }
             Some(act.unwrap().action.to_string())
         }
-    };
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{collections::HashMap, sync::Arc};
+
+    /// Application state backed by a migrated in-memory database
+    pub(crate) async fn app_state_with_database() -> Arc<AppState> {
+        let db_pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&db_pool).await.unwrap();
+
+        Arc::new(AppState {
+            db_pool,
+            host_name: "http://localhost:9090".to_string(),
+            enforce_quotas: false,
+            queues: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        })
+    }
+
+    #[test]
+    fn test_target_queue_name() {
+        for queue_path in ["/", ""] {
+            let err = target_queue_name(None, queue_path).unwrap_err();
+            assert_eq!(err.code, "MissingParameter");
+        }
+
+        assert_eq!(target_queue_name(None, "/test").unwrap(), "test");
+        assert_eq!(
+            target_queue_name(None, "/000000000000/test").unwrap(),
+            "test"
+        );
+        assert_eq!(
+            target_queue_name(Some("http://localhost:9090/from-url"), "/from-path").unwrap(),
+            "from-url"
+        );
+        assert_eq!(
+            target_queue_name(Some("http://localhost:9090/000000000000/from-url"), "/").unwrap(),
+            "from-url"
+        );
+
+        for queue_url in ["http://localhost:9090/", "http://localhost:9090/a/b/c"] {
+            let err = target_queue_name(Some(queue_url), "/test").unwrap_err();
+            assert_eq!(err.code, "AWS.SimpleQueueService.NonExistentQueue");
+        }
+    }
 }
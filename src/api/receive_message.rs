@@ -1,13 +1,25 @@
 use actix_web::{web, HttpResponse};
-use serde::Serialize;
-use tracing::error;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
+use super::{error::SqsError, helpers, message_attributes};
 use crate::AppState;
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ReceiveMessageParams {
+    queue_url: Option<String>,
+    visibility_timeout: Option<String>,
+    wait_time_seconds: Option<String>,
+    max_number_of_messages: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct ReceiveMessageResponse {
@@ -27,11 +39,18 @@ struct ReceiveMessageResult {
 struct Message {
     message_id: String,
     receipt_handle: String,
+    #[serde(rename = "MD5OfBody")]
     md5_of_body: String,
     body: String,
-    attributes: HashMap<String, String>,
-    md5_of_message_attributes: String,
-    message_attributes: HashMap<String, String>,
+    #[serde(rename = "Attribute")]
+    attributes: Vec<helpers::ParamValues>,
+    #[serde(
+        rename = "MD5OfMessageAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    md5_of_message_attributes: Option<String>,
+    #[serde(rename = "MessageAttribute")]
+    message_attributes: Vec<message_attributes::MessageAttributeEntry>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -40,45 +59,372 @@ struct ResponseMetadata {
     request_id: String,
 }
 
+/// System attributes a receive can ask for by `AttributeName.N` or `MessageSystemAttributeName.N`
+const SYSTEM_ATTRIBUTES: [&str; 8] = [
+    "AWSTraceHeader",
+    "ApproximateFirstReceiveTimestamp",
+    "ApproximateReceiveCount",
+    "MessageDeduplicationId",
+    "MessageGroupId",
+    "SenderId",
+    "SentTimestamp",
+    "SequenceNumber",
+];
+
+/// The requested system attributes of a message, sorted by name. Unknown names are ignored.
+fn system_attributes(
+    message: &crate::queue::Message,
+    names: &[String],
+) -> Vec<helpers::ParamValues> {
+    let all = names.iter().any(|name| name == "All");
+    SYSTEM_ATTRIBUTES
+        .iter()
+        .filter(|name| all || names.iter().any(|requested| requested == *name))
+        .filter_map(|name| {
+            let value = match *name {
+                "AWSTraceHeader" => message
+                    .system_attributes
+                    .get(*name)
+                    .and_then(|attribute| attribute.string_value.clone()),
+                "ApproximateFirstReceiveTimestamp" => message
+                    .first_receive_timestamp
+                    .map(|timestamp| timestamp.to_string()),
+                "ApproximateReceiveCount" => Some(message.receive_count.to_string()),
+                "MessageDeduplicationId" => message.message_deduplication_id.clone(),
+                "MessageGroupId" => message.message_group_id.clone(),
+                "SenderId" => Some(crate::service::queue::ACCOUNT_ID.to_string()),
+                "SentTimestamp" => Some(message.sent_timestamp.to_string()),
+                "SequenceNumber" => message.sequence_number.clone(),
+                _ => None,
+            }?;
+
+            Some(helpers::ParamValues {
+                name: name.to_string(),
+                value,
+            })
+        })
+        .collect()
+}
+
+/// A single receive hands out at most this many messages
+const MAX_NUMBER_OF_MESSAGES: usize = 10;
+
+fn parse_max_number_of_messages(value: &str) -> Result<usize, SqsError> {
+    match value.trim().parse::<usize>() {
+        Ok(max) if (1..=MAX_NUMBER_OF_MESSAGES).contains(&max) => Ok(max),
+        _ => Err(SqsError::invalid_parameter_value(&format!(
+            "Value {} for parameter MaxNumberOfMessages is invalid. Reason: Must be between 1 and {}, if provided.",
+            value, MAX_NUMBER_OF_MESSAGES
+        ))),
+    }
+}
+
+fn parse_wait_time_seconds(value: &str) -> Result<Duration, SqsError> {
+    match value.trim().parse::<u64>() {
+        Ok(seconds) if seconds <= crate::queue::MAX_WAIT_TIME_SECONDS => {
+            Ok(Duration::from_secs(seconds))
+        }
+        _ => Err(SqsError::invalid_parameter_value(&format!(
+            "Value {} for parameter WaitTimeSeconds is invalid. Reason: Must be >= 0 and <= {}, if provided.",
+            value,
+            crate::queue::MAX_WAIT_TIME_SECONDS
+        ))),
+    }
+}
+
+/// Receive up to `max` messages, waiting up to `wait_time` (or the queue's
+/// ReceiveMessageWaitTimeSeconds) for at least one to become available.
+/// The lock is released while waiting, pushes and expiring visibility timeouts wake us up.
+async fn receive_with_wait(
+    app_state: &AppState,
+    queue_name: &str,
+    max: usize,
+    visibility_timeout: Option<Duration>,
+    wait_time: Option<Duration>,
+) -> Result<Vec<(String, crate::queue::Message)>, SqsError> {
+    let mut deadline = None;
+
+    loop {
+        let mut queues = app_state.queues.lock().await;
+        let queue = match (*queues).get_mut(queue_name) {
+            Some(queue) => queue,
+            None => return Err(SqsError::non_existent_queue()),
+        };
+
+        let received = queue.receive_batch(max, visibility_timeout);
+        if !received.is_empty() {
+            return Ok(received);
+        }
+
+        let now = Instant::now();
+        let deadline =
+            *deadline.get_or_insert_with(|| now + wait_time.unwrap_or(queue.receive_wait_time()));
+        if deadline <= now {
+            return Ok(received);
+        }
+        let wake_at = queue
+            .next_visible_at()
+            .map_or(deadline, |visible_at| visible_at.min(deadline));
+
+        // Register for notifications before unlocking, so a push right after is not missed
+        let notify = queue.notifier();
+        let notified = notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        drop(queues);
+
+        tokio::select! {
+            _ = notified => {}
+            _ = tokio::time::sleep_until(wake_at.into()) => {}
+        }
+    }
+}
+
 pub async fn process(
     app_state: Arc<AppState>,
-    _payload: &web::Bytes,
+    payload: &web::Bytes,
+    queue_path: &str,
     _is_json: bool,
 ) -> HttpResponse {
-    let mut reader = app_state.queues.lock().await;
+    let payload = match super::struct_from_url_encode::<ReceiveMessageParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let visibility_timeout = match payload.visibility_timeout.as_deref() {
+        Some(value) => match super::change_message_visibility::parse_visibility_timeout(value) {
+            Ok(timeout) => Some(timeout),
+            Err(e) => return e.response(),
+        },
+        None => None,
+    };
+
+    let max = match payload.max_number_of_messages.as_deref() {
+        Some(value) => match parse_max_number_of_messages(value) {
+            Ok(max) => max,
+            Err(e) => return e.response(),
+        },
+        None => 1,
+    };
+
+    let wait_time = match payload.wait_time_seconds.as_deref() {
+        Some(value) => match parse_wait_time_seconds(value) {
+            Ok(wait_time) => Some(wait_time),
+            Err(e) => return e.response(),
+        },
+        None => None,
+    };
+
+    let received = match receive_with_wait(
+        &app_state,
+        &queue_name,
+        max,
+        visibility_timeout,
+        wait_time,
+    )
+    .await
+    {
+        Ok(received) => received,
+        Err(e) => return e.response(),
+    };
+    let attribute_names = helpers::extract_indexed_values("MessageAttributeName", &payload.extra);
+    let mut system_attribute_names =
+        helpers::extract_indexed_values("AttributeName", &payload.extra);
+    system_attribute_names.extend(helpers::extract_indexed_values(
+        "MessageSystemAttributeName",
+        &payload.extra,
+    ));
+    let messages = received
+        .into_iter()
+        .map(|(receipt_handle, msg)| {
+            let selected: Vec<_> = msg
+                .message_attributes
+                .iter()
+                .filter(|(name, _)| message_attributes::is_selected(name, &attribute_names))
+                .collect();
 
-    let message = (*reader).get_mut("myqueue").unwrap().pop();
-    let messages = match message {
-        Some(msg) => vec![
             Message {
                 message_id: msg.id.to_string(),
-                receipt_handle: "".to_string(),
-                md5_of_body: crate::api::helpers::compute_md5(msg.message_body.as_str()),
+                receipt_handle,
+                md5_of_body: helpers::compute_md5(msg.message_body.as_str()),
                 body: msg.message_body.to_string(),
-                attributes: HashMap::new(),
-                md5_of_message_attributes: "".to_string(),
-                message_attributes: HashMap::new(),
+                attributes: system_attributes(&msg, &system_attribute_names),
+                md5_of_message_attributes: (!selected.is_empty())
+                    .then(|| helpers::compute_md5_of_message_attributes(selected.clone())),
+                message_attributes: selected
+                    .into_iter()
+                    .map(|(name, attribute)| {
+                        message_attributes::MessageAttributeEntry::new(name, attribute)
+                    })
+                    .collect(),
             }
-        ],
-        None => {
-            error!("No messages in queue");
-            return HttpResponse::Ok().body("<ReceiveMessageResponse></ReceiveMessageResponse>");
-        }
-    };
+        })
+        .collect();
 
     let response = ReceiveMessageResponse {
         receive_message_result: ReceiveMessageResult {
             messages,
         },
         response_metadata: ResponseMetadata {
-            request_id: crate::api::helpers::generate_random_uuid4(),
+            request_id: helpers::generate_random_uuid4(),
         },
     };
 
-    return match quick_xml::se::to_string(&response) {
+    match quick_xml::se::to_string(&response) {
         Ok(resp) => HttpResponse::Ok().body(resp),
         Err(e) => {
             HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
         }
-    };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_state_with_queue(name: &str) -> AppState {
+        let mut queues = HashMap::new();
+        queues.insert(name.to_string(), crate::queue::Queue::new());
+
+        AppState {
+            db_pool: sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap(),
+            host_name: "http://localhost:9090".to_string(),
+            enforce_quotas: false,
+            queues: Arc::new(tokio::sync::Mutex::new(queues)),
+        }
+    }
+
+    #[test]
+    fn test_system_attributes() {
+        let mut message = crate::queue::Message::new("id", "message_body");
+        message.receive_count = 2;
+        message.first_receive_timestamp = Some(message.sent_timestamp + 5);
+
+        let names = |attributes: Vec<helpers::ParamValues>| -> Vec<String> {
+            attributes
+                .into_iter()
+                .map(|attribute| attribute.name)
+                .collect()
+        };
+        assert_eq!(
+            names(system_attributes(&message, &["All".to_string()])),
+            vec![
+                "ApproximateFirstReceiveTimestamp",
+                "ApproximateReceiveCount",
+                "SenderId",
+                "SentTimestamp"
+            ]
+        );
+
+        let selected = system_attributes(
+            &message,
+            &["ApproximateReceiveCount".to_string(), "Unknown".to_string()],
+        );
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].value, "2");
+
+        message.message_group_id = Some("group".to_string());
+        assert_eq!(
+            names(system_attributes(&message, &["MessageGroupId".to_string()])),
+            vec!["MessageGroupId"]
+        );
+        assert!(system_attributes(&message, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_max_number_of_messages() {
+        assert_eq!(parse_max_number_of_messages("10").unwrap(), 10);
+        assert!(parse_max_number_of_messages("0").is_err());
+        assert!(parse_max_number_of_messages("11").is_err());
+    }
+
+    #[test]
+    fn test_parse_wait_time_seconds() {
+        assert_eq!(
+            parse_wait_time_seconds("20").unwrap(),
+            Duration::from_secs(20)
+        );
+        assert!(parse_wait_time_seconds("21").is_err());
+        assert!(parse_wait_time_seconds("-1").is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_receive_with_wait_times_out() {
+        let app_state = app_state_with_queue("test");
+
+        let started = Instant::now();
+        let received = receive_with_wait(
+            &app_state,
+            "test",
+            1,
+            None,
+            Some(Duration::from_millis(100)),
+        )
+        .await
+        .unwrap();
+        assert!(received.is_empty());
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        let err = receive_with_wait(&app_state, "missing", 1, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "AWS.SimpleQueueService.NonExistentQueue");
+    }
+
+    #[actix_web::test]
+    async fn test_receive_with_wait_wakes_up_on_push() {
+        let app_state = Arc::new(app_state_with_queue("test"));
+
+        let sender = app_state.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let mut queues = sender.queues.lock().await;
+            let queue = queues.get_mut("test").unwrap();
+            queue.push(crate::queue::Message::new("id", "message_body"));
+        });
+
+        let started = Instant::now();
+        let received =
+            receive_with_wait(&app_state, "test", 10, None, Some(Duration::from_secs(5)))
+                .await
+                .unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].1.id, "id");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[actix_web::test]
+    async fn test_receive_with_wait_wakes_up_on_shorter_visibility() {
+        let app_state = Arc::new(app_state_with_queue("test"));
+        let receipt_handle = {
+            let mut queues = app_state.queues.lock().await;
+            let queue = queues.get_mut("test").unwrap();
+            queue.push(crate::queue::Message::new("id", "message_body"));
+            queue.receive(Some(Duration::from_secs(60))).unwrap().0
+        };
+
+        let changer = app_state.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let mut queues = changer.queues.lock().await;
+            let queue = queues.get_mut("test").unwrap();
+            queue
+                .change_visibility(&receipt_handle, Duration::from_millis(50))
+                .unwrap();
+        });
+
+        let started = Instant::now();
+        let received = receive_with_wait(&app_state, "test", 1, None, Some(Duration::from_secs(5)))
+            .await
+            .unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
 }
@@ -1,22 +1,29 @@
 use actix_web::{web, HttpResponse};
-use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::MutexGuard;
+use tracing::error;
 
-use super::helpers;
-use crate::AppState;
+use super::{error::SqsError, helpers, message_attributes};
+use crate::{
+    queue::{MAX_DELAY_SECONDS, SEQUENCE_NUMBER_BLOCK},
+    AppState,
+};
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct SendMessageParams {
+    queue_url: Option<String>,
     message_body: String,
-    delay_seconds: Option<i32>,
+    delay_seconds: Option<String>,
+    message_group_id: Option<String>,
+    message_deduplication_id: Option<String>,
     #[serde(flatten)]
     extra: HashMap<String, String>,
-
-    #[serde(skip)]
-    /// This will be populated when you call populate attributes method
-    attributes: Option<Vec<helpers::ParamValues>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -28,9 +35,23 @@ struct SendMessageResponse {
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "PascalCase")]
-struct SendMessageResult {
-    message_id: String,
-    md5_of_message_body: String,
+pub(super) struct SendMessageResult {
+    pub message_id: String,
+    #[serde(rename = "MD5OfMessageBody")]
+    pub md5_of_message_body: String,
+    #[serde(
+        rename = "MD5OfMessageAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub md5_of_message_attributes: Option<String>,
+    #[serde(
+        rename = "MD5OfMessageSystemAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub md5_of_message_system_attributes: Option<String>,
+    /// Only FIFO queues hand out sequence numbers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence_number: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -39,55 +60,668 @@ struct ResponseMetadata {
     request_id: String,
 }
 
+/// A single message as it is sent by SendMessage or one SendMessageBatch entry
+#[derive(Debug, Clone, Default)]
+pub(super) struct MessageInput {
+    pub message_body: Option<String>,
+    pub delay_seconds: Option<String>,
+    pub message_group_id: Option<String>,
+    pub message_deduplication_id: Option<String>,
+    /// The `MessageAttribute.N.*` parameters of the message
+    pub message_attributes: HashMap<String, String>,
+    /// The `MessageSystemAttribute.N.*` parameters of the message
+    pub message_system_attributes: HashMap<String, String>,
+}
+
+impl MessageInput {
+    /// Read a message from the parameters of a SendMessage call or a batch entry
+    pub fn from_fields(fields: &HashMap<String, String>) -> Self {
+        MessageInput {
+            message_body: fields.get("MessageBody").cloned(),
+            delay_seconds: fields.get("DelaySeconds").cloned(),
+            message_group_id: fields.get("MessageGroupId").cloned(),
+            message_deduplication_id: fields.get("MessageDeduplicationId").cloned(),
+            message_attributes: fields
+                .iter()
+                .filter(|(key, _)| key.starts_with("MessageAttribute."))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            message_system_attributes: fields
+                .iter()
+                .filter(|(key, _)| key.starts_with("MessageSystemAttribute."))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Size of the message as SQS counts it, the body plus all attribute names, types and
+    /// decoded values. Invalid attributes fail the send later and are not counted.
+    pub fn size(&self) -> usize {
+        self.message_body.as_ref().map_or(0, |body| body.len())
+            + message_attributes::parse(&self.message_attributes)
+                .map_or(0, |attributes| message_attributes::size(&attributes))
+    }
+}
+
 impl SendMessageParams {
-    /// Populate the attributes from the extra hashmap
-    fn populate_attributes(&mut self) {
-        let re = RegexBuilder::new(r"^Attribute\.(\d+)\.(.+)$")
-            .case_insensitive(true)
-            .build()
-            .unwrap();
+    fn message_input(&self) -> MessageInput {
+        let mut input = MessageInput::from_fields(&self.extra);
+        input.message_body = Some(self.message_body.clone());
+        input.delay_seconds = self.delay_seconds.clone();
+        input.message_group_id = self.message_group_id.clone();
+        input.message_deduplication_id = self.message_deduplication_id.clone();
+        input
+    }
+}
+
+/// Characters SQS accepts in a message body, the XML 1.0 character range
+fn is_allowed_character(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..='\u{10FFFF}'
+    )
+}
+
+/// Message group and deduplication ids are 1 to 128 alphanumeric or punctuation characters
+fn validate_fifo_id(parameter: &str, value: &str) -> Result<(), SqsError> {
+    let is_valid = !value.is_empty()
+        && value.len() <= 128
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c.is_ascii_punctuation());
+    if !is_valid {
+        return Err(SqsError::invalid_parameter_value(&format!(
+            "Value {} for parameter {} is invalid. Reason: Must be 1 to 128 alphanumeric or punctuation characters.",
+            value, parameter
+        )));
+    }
+
+    Ok(())
+}
+
+/// FIFO queues need a message group and either a deduplication id or content based
+/// deduplication, and only support the queue wide delay. Standard queues don't take
+/// deduplication ids.
+fn validate_fifo_parameters(
+    queue: &crate::queue::Queue,
+    input: &MessageInput,
+) -> Result<(), SqsError> {
+    let not_valid_for_queue_type = |parameter: &str, value: &str| {
+        SqsError::invalid_parameter_value(&format!(
+            "Value {} for parameter {} is invalid. Reason: The request include parameter that is not valid for this queue type.",
+            value, parameter
+        ))
+    };
+
+    if let Some(message_group_id) = &input.message_group_id {
+        validate_fifo_id("MessageGroupId", message_group_id)?;
+    }
+    if let Some(message_deduplication_id) = &input.message_deduplication_id {
+        if !queue.is_fifo() {
+            return Err(not_valid_for_queue_type(
+                "MessageDeduplicationId",
+                message_deduplication_id,
+            ));
+        }
+        validate_fifo_id("MessageDeduplicationId", message_deduplication_id)?;
+    }
+    if !queue.is_fifo() {
+        return Ok(());
+    }
+
+    if input.message_group_id.is_none() {
+        return Err(SqsError::missing_parameter("MessageGroupId"));
+    }
+    if input.message_deduplication_id.is_none() && !queue.content_based_deduplication() {
+        return Err(SqsError::invalid_parameter_value(
+            "The queue should either have ContentBasedDeduplication enabled or MessageDeduplicationId provided explicitly",
+        ));
+    }
+    if let Some(delay_seconds) = &input.delay_seconds {
+        return Err(not_valid_for_queue_type("DelaySeconds", delay_seconds));
+    }
+
+    Ok(())
+}
+
+/// Validate a message and put it on the queue. `counted_sends` holds the throughput quotas
+/// this call was already counted towards, it is None when quotas are not enforced.
+pub(super) fn enqueue(
+    queue: &mut crate::queue::Queue,
+    input: MessageInput,
+    counted_sends: Option<&mut Vec<Option<String>>>,
+) -> Result<SendMessageResult, SqsError> {
+    let message_body = match &input.message_body {
+        Some(body) if !body.is_empty() => body.clone(),
+        Some(_) => {
+            return Err(SqsError::invalid_parameter_value(
+                "The request must contain a non-empty message body.",
+            ))
+        }
+        None => return Err(SqsError::missing_parameter("MessageBody")),
+    };
+
+    if let Some(character) = message_body.chars().find(|c| !is_allowed_character(*c)) {
+        return Err(SqsError::invalid_message_contents(character));
+    }
+
+    let attributes = message_attributes::parse(&input.message_attributes)?;
+    let size = message_body.len() + message_attributes::size(&attributes);
+    let maximum_message_size = queue.maximum_message_size();
+    if size > maximum_message_size {
+        return Err(SqsError::invalid_parameter_value(&format!(
+            "One or more parameters are invalid. Reason: Message must be shorter than {} bytes.",
+            maximum_message_size
+        )));
+    }
+
+    validate_fifo_parameters(queue, &input)?;
+    let system_attributes = message_attributes::parse_system(&input.message_system_attributes)?;
+
+    let delay = match &input.delay_seconds {
+        Some(delay_seconds) => match delay_seconds.trim().parse::<u64>() {
+            Ok(seconds) if seconds <= MAX_DELAY_SECONDS => Duration::from_secs(seconds),
+            _ => {
+                return Err(SqsError::invalid_parameter_value(&format!(
+                    "Value {} for parameter DelaySeconds is invalid. Reason: Must be >= 0 and <= {}, if provided.",
+                    delay_seconds, MAX_DELAY_SECONDS
+                )))
+            }
+        },
+        None => queue.delay(),
+    };
+
+    // FIFO sends within the deduplication interval of an earlier one with the same id
+    // are accepted but not enqueued, the original message id is returned
+    let deduplication_id = match &input.message_deduplication_id {
+        Some(id) => Some(id.clone()),
+        None if queue.is_fifo() => Some(helpers::compute_sha256(&message_body)),
+        None => None,
+    };
+    let md5_of_attributes = |attributes: &BTreeMap<String, crate::queue::MessageAttribute>| {
+        (!attributes.is_empty()).then(|| helpers::compute_md5_of_message_attributes(attributes))
+    };
+    let original = deduplication_id
+        .as_deref()
+        .and_then(|id| queue.find_duplicate(input.message_group_id.as_deref(), id));
+    let is_duplicate = original.is_some();
+
+    // Only sends which are accepted and not deduplicated count towards the quota, a call
+    // counts once per quota however many of its messages share it
+    if let Some(counted_sends) = counted_sends.filter(|_| queue.is_fifo() && !is_duplicate) {
+        let key = queue.throughput_key(input.message_group_id.as_deref());
+        if !counted_sends.contains(&key) {
+            if !queue.acquire_send(&key, Instant::now()) {
+                return Err(SqsError::request_throttled());
+            }
+            counted_sends.push(key);
+        }
+    }
+
+    let (msg_id, sequence_number) = match original {
+        Some(original) => original,
+        None => (
+            helpers::generate_random_uuid4(),
+            queue.is_fifo().then(|| queue.next_sequence_number()),
+        ),
+    };
+    let result = SendMessageResult {
+        message_id: msg_id.clone(),
+        md5_of_message_body: helpers::compute_md5(message_body.as_str()),
+        md5_of_message_attributes: md5_of_attributes(&attributes),
+        md5_of_message_system_attributes: md5_of_attributes(&system_attributes),
+        sequence_number: sequence_number.clone(),
+    };
+    if is_duplicate {
+        return Ok(result);
+    }
+
+    let mut message = crate::queue::Message::new(&msg_id, &message_body);
+    message.message_attributes = attributes;
+    message.system_attributes = system_attributes;
+    message.message_group_id = input.message_group_id;
+    message.sequence_number = sequence_number;
+    if let Some(deduplication_id) = &deduplication_id {
+        queue.record_deduplication(deduplication_id, &message);
+    }
+    message.message_deduplication_id = deduplication_id;
+    queue.push_delayed(message, delay);
+
+    Ok(result)
+}
+
+/// Lock the queues once a FIFO queue has `count` persisted sequence numbers left for a send.
+/// A new block is reserved in the database without holding the lock, so a message is only
+/// enqueued with a number that keeps increasing across restarts.
+pub(super) async fn lock_with_sequence_numbers<'a>(
+    app_state: &'a AppState,
+    queue_name: &str,
+    count: usize,
+) -> Result<MutexGuard<'a, HashMap<String, crate::queue::Queue>>, HttpResponse> {
+    let count = count as i64;
+    loop {
+        let reserve_up_to = {
+            let writer = app_state.queues.lock().await;
+            match (*writer).get(queue_name) {
+                Some(queue) if queue.is_fifo() && queue.sequence_numbers_left() < count => {
+                    queue.reserved_sequence_number() + count + SEQUENCE_NUMBER_BLOCK
+                }
+                _ => return Ok(writer),
+            }
+        };
+
+        let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+        if let Err(e) = service.set_sequence_number(queue_name, reserve_up_to).await {
+            error!("Failed to reserve sequence numbers: {}", e);
+            return Err(HttpResponse::InternalServerError()
+                .body(format!("Failed to reserve sequence numbers: {}", e)));
+        }
 
-        self.attributes = helpers::extract_from_extra(re, self.extra.clone());
+        let mut writer = app_state.queues.lock().await;
+        if let Some(queue) = (*writer).get_mut(queue_name) {
+            queue.reserve_sequence_numbers(reserve_up_to);
+        }
     }
 }
 
 pub async fn process(
     app_state: Arc<AppState>,
     payload: &web::Bytes,
+    queue_path: &str,
     _is_json: bool,
 ) -> HttpResponse {
-    let mut payload = match super::struct_from_url_encode::<SendMessageParams>(payload) {
+    let payload = match super::struct_from_url_encode::<SendMessageParams>(payload) {
         Ok(p) => p,
         Err(e) => {
             return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
         }
     };
-    payload.populate_attributes();
-
-    let msg_id = helpers::generate_random_uuid4();
-    let mut writer = app_state.queues.lock().await;
-    (*writer)
-        .get_mut("myqueue")
-        .unwrap()
-        .push(crate::queue::Message {
-            id: msg_id.clone(),
-            message_body: payload.message_body.clone(),
-        });
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let mut writer = match lock_with_sequence_numbers(&app_state, &queue_name, 1).await {
+        Ok(writer) => writer,
+        Err(response) => return response,
+    };
+    let queue = match (*writer).get_mut(&queue_name) {
+        Some(queue) => queue,
+        None => return SqsError::non_existent_queue().response(),
+    };
+    let mut counted_sends = vec![];
+    let counted_sends = app_state.enforce_quotas.then_some(&mut counted_sends);
+    let result = match enqueue(queue, payload.message_input(), counted_sends) {
+        Ok(result) => result,
+        Err(e) => return e.response(),
+    };
 
     let response = SendMessageResponse {
-        send_message_result: SendMessageResult {
-            message_id: msg_id.clone(),
-            md5_of_message_body: helpers::compute_md5(payload.message_body.clone().as_str()),
-        },
+        send_message_result: result,
         reponse_metadata: ResponseMetadata {
             request_id: helpers::generate_random_uuid4(),
         },
     };
 
-    return match quick_xml::se::to_string(&response) {
+    match quick_xml::se::to_string(&response) {
         Ok(resp) => HttpResponse::Ok().body(resp),
         Err(e) => {
             HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
         }
-    };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enqueue() {
+        let mut queue = crate::queue::Queue::new();
+        let result = enqueue(
+            &mut queue,
+            MessageInput {
+                message_body: Some("hello".to_string()),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            result.md5_of_message_body,
+            "5d41402abc4b2a76b9719d911017c592"
+        );
+
+        let (_, message) = queue.receive(None).unwrap();
+        assert_eq!(message.id, result.message_id);
+    }
+
+    #[test]
+    fn test_enqueue_attributes() {
+        let mut queue = crate::queue::Queue::new();
+        let mut fields = HashMap::new();
+        fields.insert("MessageBody".to_string(), "hello".to_string());
+        let result = enqueue(&mut queue, MessageInput::from_fields(&fields), None).unwrap();
+        assert_eq!(result.md5_of_message_attributes, None);
+        assert_eq!(result.md5_of_message_system_attributes, None);
+
+        fields.insert("MessageAttribute.1.Name".to_string(), "test".to_string());
+        fields.insert(
+            "MessageAttribute.1.Value.DataType".to_string(),
+            "String".to_string(),
+        );
+        fields.insert(
+            "MessageAttribute.1.Value.StringValue".to_string(),
+            "test".to_string(),
+        );
+        fields.insert(
+            "MessageSystemAttribute.1.Name".to_string(),
+            "AWSTraceHeader".to_string(),
+        );
+        fields.insert(
+            "MessageSystemAttribute.1.Value.DataType".to_string(),
+            "String".to_string(),
+        );
+        fields.insert(
+            "MessageSystemAttribute.1.Value.StringValue".to_string(),
+            "Root=1".to_string(),
+        );
+        let result = enqueue(&mut queue, MessageInput::from_fields(&fields), None).unwrap();
+        assert_eq!(
+            result.md5_of_message_attributes.as_deref(),
+            Some("ddb45ae313fa7f1b0fbf07d6f3b9e1c5")
+        );
+        assert!(result.md5_of_message_system_attributes.is_some());
+
+        let received = queue.receive_batch(10, None);
+        assert!(received[0].1.message_attributes.is_empty());
+        assert_eq!(received[1].1.message_attributes["test"].data_type, "String");
+        assert!(received[1]
+            .1
+            .system_attributes
+            .contains_key("AWSTraceHeader"));
+    }
+
+    #[test]
+    fn test_enqueue_invalid() {
+        let mut queue = crate::queue::Queue::new();
+        let err = enqueue(&mut queue, MessageInput::default(), None).unwrap_err();
+        assert_eq!(err.code, "MissingParameter");
+
+        let err = enqueue(
+            &mut queue,
+            MessageInput {
+                message_body: Some("hello".to_string()),
+                delay_seconds: Some("soon".to_string()),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+
+        let err = enqueue(
+            &mut queue,
+            MessageInput {
+                message_body: Some("hello".to_string()),
+                delay_seconds: Some("901".to_string()),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+        assert!(queue.receive(None).is_none());
+    }
+
+    #[test]
+    fn test_enqueue_size_and_contents() {
+        let mut queue = crate::queue::Queue::new();
+        queue.set_attributes(HashMap::from([(
+            "MaximumMessageSize".to_string(),
+            "1024".to_string(),
+        )]));
+        let message = |body: String| MessageInput {
+            message_body: Some(body),
+            ..Default::default()
+        };
+
+        assert!(enqueue(&mut queue, message("a".repeat(1024)), None).is_ok());
+        let err = enqueue(&mut queue, message("a".repeat(1025)), None).unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+
+        let err = enqueue(&mut queue, message("bell \u{7}".to_string()), None).unwrap_err();
+        assert_eq!(err.code, "InvalidMessageContents");
+        assert!(err.message.contains("#x7"));
+        assert!(enqueue(&mut queue, message("tab\there \u{1F600}".to_string()), None).is_ok());
+
+        // 990 + "blob" + "Binary" + 24 bytes fits, the 32 bytes of base64 would not
+        let mut with_binary = message("a".repeat(990));
+        with_binary.message_attributes = HashMap::from([
+            ("MessageAttribute.1.Name".to_string(), "blob".to_string()),
+            (
+                "MessageAttribute.1.Value.DataType".to_string(),
+                "Binary".to_string(),
+            ),
+            (
+                "MessageAttribute.1.Value.BinaryValue".to_string(),
+                "A".repeat(32),
+            ),
+        ]);
+        assert_eq!(with_binary.size(), 990 + 4 + 6 + 24);
+        assert!(enqueue(&mut queue, with_binary, None).is_ok());
+    }
+
+    #[test]
+    fn test_enqueue_fifo() {
+        let mut queue = crate::queue::Queue::new();
+        queue.set_attributes(HashMap::from([(
+            "FifoQueue".to_string(),
+            "true".to_string(),
+        )]));
+        let message = |group: Option<&str>, deduplication: Option<&str>| MessageInput {
+            message_body: Some("hello".to_string()),
+            message_group_id: group.map(|id| id.to_string()),
+            message_deduplication_id: deduplication.map(|id| id.to_string()),
+            ..Default::default()
+        };
+
+        let err = enqueue(&mut queue, message(None, Some("dedup")), None).unwrap_err();
+        assert_eq!(err.code, "MissingParameter");
+        let err = enqueue(&mut queue, message(Some("group"), None), None).unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+        let err = enqueue(&mut queue, message(Some("bad group"), Some("dedup")), None).unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+        let mut delayed = message(Some("group"), Some("dedup"));
+        delayed.delay_seconds = Some("5".to_string());
+        let err = enqueue(&mut queue, delayed, None).unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+        assert!(enqueue(&mut queue, message(Some("group"), Some("dedup")), None).is_ok());
+
+        queue.set_attributes(HashMap::from([(
+            "ContentBasedDeduplication".to_string(),
+            "true".to_string(),
+        )]));
+        assert!(enqueue(&mut queue, message(Some("group"), None), None).is_ok());
+
+        let mut standard = crate::queue::Queue::new();
+        let err = enqueue(&mut standard, message(None, Some("dedup")), None).unwrap_err();
+        assert_eq!(err.code, "InvalidParameterValue");
+        assert!(enqueue(&mut standard, message(Some("group"), None), None).is_ok());
+    }
+
+    #[test]
+    fn test_enqueue_deduplication() {
+        let mut queue = crate::queue::Queue::new();
+        queue.set_attributes(HashMap::from([
+            ("FifoQueue".to_string(), "true".to_string()),
+            ("ContentBasedDeduplication".to_string(), "true".to_string()),
+        ]));
+        let message = |body: &str, deduplication: Option<&str>| MessageInput {
+            message_body: Some(body.to_string()),
+            message_group_id: Some("group".to_string()),
+            message_deduplication_id: deduplication.map(|id| id.to_string()),
+            ..Default::default()
+        };
+
+        let first = enqueue(&mut queue, message("hello", Some("dedup")), None).unwrap();
+        let duplicate = enqueue(&mut queue, message("other body", Some("dedup")), None).unwrap();
+        assert_eq!(duplicate.message_id, first.message_id);
+        assert_eq!(duplicate.sequence_number, first.sequence_number);
+
+        let by_content = enqueue(&mut queue, message("hello", None), None).unwrap();
+        assert_ne!(by_content.message_id, first.message_id);
+        let duplicate = enqueue(&mut queue, message("hello", None), None).unwrap();
+        assert_eq!(duplicate.message_id, by_content.message_id);
+        assert_eq!(
+            first.sequence_number.as_deref(),
+            Some("00000000000000000001")
+        );
+        assert_eq!(
+            by_content.sequence_number.as_deref(),
+            Some("00000000000000000002")
+        );
+
+        let received = queue.receive_batch(10, None);
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[1].1.sequence_number, by_content.sequence_number);
+        assert_eq!(
+            received[1].1.message_deduplication_id.as_deref(),
+            Some(helpers::compute_sha256("hello").as_str())
+        );
+    }
+
+    #[test]
+    fn test_enqueue_quota() {
+        let mut queue = crate::queue::Queue::new();
+        queue.set_attributes(HashMap::from([
+            ("FifoQueue".to_string(), "true".to_string()),
+            ("ContentBasedDeduplication".to_string(), "true".to_string()),
+        ]));
+        let message = |body: &str, group: Option<&str>| MessageInput {
+            message_body: Some(body.to_string()),
+            message_group_id: group.map(|group| group.to_string()),
+            ..Default::default()
+        };
+
+        let mut counted_sends = vec![];
+        let err = enqueue(&mut queue, message("a", None), Some(&mut counted_sends)).unwrap_err();
+        assert_eq!(err.code, "MissingParameter");
+        assert!(counted_sends.is_empty());
+
+        let key = queue.throughput_key(Some("group"));
+        for _ in 0..crate::queue::MAX_FIFO_SENDS_PER_SECOND - 2 {
+            assert!(queue.acquire_send(&key, Instant::now()));
+        }
+
+        // Several messages of one call count once, a deduplicated send not at all
+        enqueue(
+            &mut queue,
+            message("a", Some("group")),
+            Some(&mut counted_sends),
+        )
+        .unwrap();
+        enqueue(
+            &mut queue,
+            message("b", Some("group")),
+            Some(&mut counted_sends),
+        )
+        .unwrap();
+        assert_eq!(counted_sends, vec![None]);
+        let mut counted_sends = vec![];
+        enqueue(
+            &mut queue,
+            message("a", Some("group")),
+            Some(&mut counted_sends),
+        )
+        .unwrap();
+        assert!(counted_sends.is_empty());
+
+        enqueue(&mut queue, message("c", Some("group")), Some(&mut vec![])).unwrap();
+        let err = enqueue(&mut queue, message("d", Some("group")), Some(&mut vec![])).unwrap_err();
+        assert_eq!(err.code, "RequestThrottled");
+        let err = enqueue(&mut queue, message("d", None), Some(&mut vec![])).unwrap_err();
+        assert_eq!(err.code, "MissingParameter");
+        assert!(enqueue(&mut queue, message("d", Some("group")), None).is_ok());
+    }
+
+    #[test]
+    fn test_enqueue_delay() {
+        let mut queue = crate::queue::Queue::new();
+        queue.set_attributes(HashMap::from([(
+            "DelaySeconds".to_string(),
+            "60".to_string(),
+        )]));
+
+        let message = |delay_seconds: Option<&str>| MessageInput {
+            message_body: Some("hello".to_string()),
+            delay_seconds: delay_seconds.map(|d| d.to_string()),
+            ..Default::default()
+        };
+        enqueue(&mut queue, message(None), None).unwrap();
+        assert!(queue.receive(None).is_none());
+        assert_eq!(queue.approximate_number_of_messages_delayed(), 1);
+
+        let result = enqueue(&mut queue, message(Some("0")), None).unwrap();
+        let (_, received) = queue.receive(None).unwrap();
+        assert_eq!(received.id, result.message_id);
+    }
+
+    #[test]
+    fn test_message_input_size() {
+        let mut fields = HashMap::new();
+        fields.insert("MessageBody".to_string(), "hello".to_string());
+        fields.insert("MessageAttribute.1.Name".to_string(), "key".to_string());
+        fields.insert(
+            "MessageAttribute.1.Value.DataType".to_string(),
+            "String".to_string(),
+        );
+        fields.insert(
+            "MessageAttribute.1.Value.StringValue".to_string(),
+            "value".to_string(),
+        );
+
+        let input = MessageInput::from_fields(&fields);
+        assert_eq!(input.size(), 5 + 3 + 6 + 5);
+    }
+
+    #[actix_web::test]
+    async fn test_lock_with_sequence_numbers() {
+        let mut queue = crate::queue::Queue::new();
+        queue.set_attributes(HashMap::from([(
+            "FifoQueue".to_string(),
+            "true".to_string(),
+        )]));
+        queue.reserve_sequence_numbers(1);
+        // The database has no queues table, any attempt to reserve more numbers fails
+        let app_state = AppState {
+            db_pool: sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap(),
+            host_name: "http://localhost:9090".to_string(),
+            enforce_quotas: false,
+            queues: Arc::new(tokio::sync::Mutex::new(HashMap::from([(
+                "test.fifo".to_string(),
+                queue,
+            )]))),
+        };
+
+        let mut writer = lock_with_sequence_numbers(&app_state, "test.fifo", 1)
+            .await
+            .unwrap();
+        let queue = writer.get_mut("test.fifo").unwrap();
+        assert_eq!(queue.next_sequence_number(), "00000000000000000001");
+        drop(writer);
+
+        let response = lock_with_sequence_numbers(&app_state, "test.fifo", 1)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            response.status(),
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            app_state.queues.lock().await["test.fifo"].sequence_numbers_left(),
+            0
+        );
+    }
 }
@@ -0,0 +1,147 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+use super::{
+    batch::{self, BatchResultErrorEntry},
+    error::SqsError,
+    helpers,
+    send_message::{self, MessageInput},
+};
+use crate::AppState;
+
+const ENTRY_PREFIX: &str = "SendMessageBatchRequestEntry";
+
+/// The sum of all message sizes in a batch can be at most 1 MiB
+const MAX_BATCH_PAYLOAD_SIZE: usize = crate::queue::MAX_MESSAGE_SIZE as usize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SendMessageBatchParams {
+    queue_url: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SendMessageBatchResponse {
+    send_message_batch_result: SendMessageBatchResult,
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SendMessageBatchResult {
+    #[serde(rename = "SendMessageBatchResultEntry")]
+    successful: Vec<SendMessageBatchResultEntry>,
+    #[serde(rename = "BatchResultErrorEntry")]
+    failed: Vec<BatchResultErrorEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SendMessageBatchResultEntry {
+    id: String,
+    message_id: String,
+    #[serde(rename = "MD5OfMessageBody")]
+    md5_of_message_body: String,
+    #[serde(
+        rename = "MD5OfMessageAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    md5_of_message_attributes: Option<String>,
+    #[serde(
+        rename = "MD5OfMessageSystemAttributes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    md5_of_message_system_attributes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sequence_number: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Send up to 10 messages, an invalid entry does not stop the others from being sent
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<SendMessageBatchParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let entries = match batch::extract_batch_entries(ENTRY_PREFIX, &payload.extra) {
+        Ok(entries) => entries,
+        Err(e) => return e.response(),
+    };
+
+    let messages: Vec<(String, MessageInput)> = entries
+        .into_iter()
+        .map(|entry| (entry.id.clone(), MessageInput::from_fields(&entry.fields)))
+        .collect();
+    let payload_size: usize = messages.iter().map(|(_, input)| input.size()).sum();
+    if payload_size > MAX_BATCH_PAYLOAD_SIZE {
+        return SqsError::batch_request_too_long(MAX_BATCH_PAYLOAD_SIZE).response();
+    }
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let mut writer =
+        match send_message::lock_with_sequence_numbers(&app_state, &queue_name, messages.len())
+            .await
+        {
+            Ok(writer) => writer,
+            Err(response) => return response,
+        };
+    let queue = match (*writer).get_mut(&queue_name) {
+        Some(queue) => queue,
+        None => return SqsError::non_existent_queue().response(),
+    };
+
+    let mut result = SendMessageBatchResult {
+        successful: vec![],
+        failed: vec![],
+    };
+    let mut counted_sends = vec![];
+    for (id, input) in messages {
+        let counted_sends = app_state.enforce_quotas.then_some(&mut counted_sends);
+        match send_message::enqueue(queue, input, counted_sends) {
+            Ok(sent) => result.successful.push(SendMessageBatchResultEntry {
+                id,
+                message_id: sent.message_id,
+                md5_of_message_body: sent.md5_of_message_body,
+                md5_of_message_attributes: sent.md5_of_message_attributes,
+                md5_of_message_system_attributes: sent.md5_of_message_system_attributes,
+                sequence_number: sent.sequence_number,
+            }),
+            Err(e) => result.failed.push(BatchResultErrorEntry::new(&id, &e)),
+        }
+    }
+
+    let response = SendMessageBatchResponse {
+        send_message_batch_result: result,
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tracing::error;
+
+use super::{
+    create_queue::{ATTR_FIFO, ATTR_LIST},
+    error::SqsError,
+    helpers,
+};
+use crate::AppState;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SetQueueAttributesParams {
+    queue_url: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SetQueueAttributesResponse {
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+fn check_range(name: &str, value: &str, min: u64, max: u64) -> Result<(), SqsError> {
+    match value.trim().parse::<u64>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(()),
+        _ => Err(SqsError::invalid_attribute_value(
+            name,
+            &format!("Must be an integer from {} to {}.", min, max),
+        )),
+    }
+}
+
+fn check_one_of(name: &str, value: &str, allowed: &[&str]) -> Result<(), SqsError> {
+    if allowed.contains(&value) {
+        Ok(())
+    } else {
+        Err(SqsError::invalid_attribute_value(
+            name,
+            &format!("Must be one of {}.", allowed.join(", ")),
+        ))
+    }
+}
+
+fn check_redrive_policy(name: &str, value: &str) -> Result<(), SqsError> {
+    let invalid = |reason: &str| SqsError::invalid_attribute_value(name, reason);
+    let policy: serde_json::Value =
+        serde_json::from_str(value).map_err(|_| invalid("Must be a valid JSON document."))?;
+
+    if !policy["deadLetterTargetArn"].is_string() {
+        return Err(invalid("Redrive policy must contain deadLetterTargetArn."));
+    }
+    let max_receive_count = match &policy["maxReceiveCount"] {
+        serde_json::Value::Number(count) => count.as_u64(),
+        serde_json::Value::String(count) => count.parse::<u64>().ok(),
+        _ => None,
+    };
+    match max_receive_count {
+        Some(count) if (1..=1000).contains(&count) => Ok(()),
+        _ => Err(invalid(
+            "Value of maxReceiveCount must be an integer from 1 to 1000.",
+        )),
+    }
+}
+
+/// Validate a queue attribute value, FIFO attributes are only accepted on FIFO queues
+pub(super) fn validate_attribute(name: &str, value: &str, is_fifo: bool) -> Result<(), SqsError> {
+    if !ATTR_LIST.contains(&name) && !ATTR_FIFO.contains(&name) {
+        return Err(SqsError::invalid_attribute_name(name));
+    }
+    if ATTR_FIFO.contains(&name) && !is_fifo {
+        return Err(SqsError::invalid_attribute_name(name));
+    }
+
+    match name {
+        "DelaySeconds" => check_range(name, value, 0, 900),
+        "MaximumMessageSize" => check_range(name, value, 1024, crate::queue::MAX_MESSAGE_SIZE),
+        "MessageRetentionPeriod" => check_range(name, value, 60, 1_209_600),
+        "ReceiveMessageWaitTimeSeconds" => check_range(name, value, 0, 20),
+        "VisibilityTimeout" => check_range(name, value, 0, crate::queue::MAX_VISIBILITY_TIMEOUT),
+        "KmsDataKeyReusePeriodSeconds" => check_range(name, value, 60, 86_400),
+        "RedrivePolicy" => check_redrive_policy(name, value),
+        "Policy" => serde_json::from_str::<serde_json::Value>(value)
+            .map(|_| ())
+            .map_err(|_| SqsError::invalid_attribute_value(name, "Must be a valid JSON document.")),
+        "SqsManagedSseEnabled" | "ContentBasedDeduplication" => {
+            check_one_of(name, value, &["true", "false"])
+        }
+        "FifoQueue" => check_one_of(name, value, &["true"]),
+        "DeduplicationScope" => check_one_of(name, value, &["messageGroup", "queue"]),
+        "FifoThroughputLimit" => check_one_of(name, value, &["perQueue", "perMessageGroupId"]),
+        _ => Ok(()),
+    }
+}
+
+/// Validate an attribute change of an existing queue, the queue type can only be chosen
+/// when the queue is created
+fn validate_change(name: &str, value: &str, is_fifo: bool) -> Result<(), SqsError> {
+    if name == "FifoQueue" {
+        return Err(SqsError::invalid_attribute_name(name));
+    }
+
+    validate_attribute(name, value, is_fifo)
+}
+
+/// High throughput mode needs deduplication per message group, checked once every attribute
+/// value of the queue is known
+pub(super) fn validate_throughput_limit(
+    attributes: &HashMap<String, String>,
+) -> Result<(), SqsError> {
+    let per_message_group = attributes
+        .get("FifoThroughputLimit")
+        .is_some_and(|value| value == "perMessageGroupId");
+    let queue_scope = attributes
+        .get("DeduplicationScope")
+        .is_none_or(|value| value == "queue");
+    if per_message_group && queue_scope {
+        return Err(SqsError::invalid_attribute_value(
+            "FifoThroughputLimit",
+            "perMessageGroupId is only supported with a messageGroup DeduplicationScope.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Change attributes of an existing queue, the new values are stored and applied right away
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<SetQueueAttributesParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    // Hold the lock from the lookup until the new values are applied, so concurrent changes
+    // are validated against each other and stored in the same order they are applied
+    let mut writer = app_state.queues.lock().await;
+    let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+    let queue = match service.get_queue(&queue_name).await {
+        Ok(Some(queue)) => queue,
+        Ok(None) => return SqsError::non_existent_queue().response(),
+        Err(e) => {
+            error!("Failed to get queue: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to get queue: {}", e));
+        }
+    };
+
+    let mut attributes = HashMap::new();
+    for entry in helpers::extract_entries("Attribute", &payload.extra) {
+        let name = entry.get("Name").cloned().unwrap_or_default();
+        let value = entry.get("Value").cloned().unwrap_or_default();
+        if let Err(e) = validate_change(&name, &value, queue.is_fifo()) {
+            return e.response();
+        }
+        attributes.insert(name, value);
+    }
+    if attributes.is_empty() {
+        return SqsError::missing_parameter("Attribute.1.Name").response();
+    }
+
+    let mut merged = match service.get_attributes(queue.id.unwrap_or_default()).await {
+        Ok(stored) => stored,
+        Err(e) => {
+            error!("Failed to get queue attributes: {}", e);
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to get queue attributes: {}", e));
+        }
+    };
+    merged.extend(attributes.clone());
+    if let Err(e) = validate_throughput_limit(&merged) {
+        return e.response();
+    }
+
+    if let Err(e) = service
+        .set_attributes(queue.id.unwrap_or_default(), attributes.clone())
+        .await
+    {
+        error!("Failed to set queue attributes: {}", e);
+        return HttpResponse::InternalServerError()
+            .body(format!("Failed to set queue attributes: {}", e));
+    }
+    if let Some(queue) = (*writer).get_mut(&queue_name) {
+        queue.set_attributes(attributes);
+    }
+
+    let response = SetQueueAttributesResponse {
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_attribute_ranges() {
+        assert!(validate_attribute("VisibilityTimeout", "43200", false).is_ok());
+        assert!(validate_attribute("VisibilityTimeout", "43201", false).is_err());
+        assert!(validate_attribute("DelaySeconds", "901", false).is_err());
+        assert!(validate_attribute("MessageRetentionPeriod", "59", false).is_err());
+        assert!(validate_attribute("ReceiveMessageWaitTimeSeconds", "20", false).is_ok());
+        assert!(validate_attribute("MaximumMessageSize", "abc", false).is_err());
+        assert!(validate_attribute("MaximumMessageSize", "1048576", false).is_ok());
+        assert!(validate_attribute("MaximumMessageSize", "1048577", false).is_err());
+    }
+
+    #[test]
+    fn test_validate_attribute_policies() {
+        let policy = r#"{"deadLetterTargetArn":"arn:aws:sqs:us-east-1:000000000000:dlq","maxReceiveCount":"5"}"#;
+        assert!(validate_attribute("RedrivePolicy", policy, false).is_ok());
+        let policy = r#"{"deadLetterTargetArn":"arn:aws:sqs:us-east-1:000000000000:dlq","maxReceiveCount":0}"#;
+        assert!(validate_attribute("RedrivePolicy", policy, false).is_err());
+        assert!(validate_attribute("Policy", "not json", false).is_err());
+    }
+
+    #[test]
+    fn test_validate_attribute_fifo_only() {
+        let err = validate_attribute("ContentBasedDeduplication", "true", false).unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeName");
+        assert!(validate_attribute("ContentBasedDeduplication", "true", true).is_ok());
+        assert!(validate_attribute("DeduplicationScope", "messageGroup", true).is_ok());
+        assert!(validate_attribute("FifoThroughputLimit", "perGroup", true).is_err());
+        assert!(validate_attribute("NOT_EXISTS", "1", true).is_err());
+    }
+
+    #[test]
+    fn test_validate_change() {
+        let err = validate_change("FifoQueue", "true", true).unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeName");
+        let err = validate_change("FifoQueue", "true", false).unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeName");
+        assert!(validate_change("ContentBasedDeduplication", "true", true).is_ok());
+        assert!(validate_change("DelaySeconds", "901", false).is_err());
+    }
+
+    #[test]
+    fn test_validate_throughput_limit() {
+        let attributes = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+
+        assert!(validate_throughput_limit(&attributes(&[])).is_ok());
+        assert!(validate_throughput_limit(&attributes(&[
+            ("DeduplicationScope", "messageGroup"),
+            ("FifoThroughputLimit", "perMessageGroupId"),
+        ]))
+        .is_ok());
+        assert!(validate_throughput_limit(&attributes(&[
+            ("DeduplicationScope", "messageGroup"),
+            ("FifoThroughputLimit", "perQueue"),
+        ]))
+        .is_ok());
+
+        let err =
+            validate_throughput_limit(&attributes(&[("FifoThroughputLimit", "perMessageGroupId")]))
+                .unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeValue");
+        let err = validate_throughput_limit(&attributes(&[
+            ("DeduplicationScope", "queue"),
+            ("FifoThroughputLimit", "perMessageGroupId"),
+        ]))
+        .unwrap_err();
+        assert_eq!(err.code, "InvalidAttributeValue");
+    }
+
+    #[actix_web::test]
+    async fn test_set_queue_attributes() {
+        let app_state = crate::api::tests::app_state_with_database().await;
+        let create = web::Bytes::from("Action=CreateQueue&QueueName=test");
+        let response = crate::api::create_queue::process(app_state.clone(), &create, false).await;
+        assert_eq!(response.status(), 200);
+
+        let set = web::Bytes::from(
+            "Action=SetQueueAttributes&Attribute.1.Name=VisibilityTimeout&Attribute.1.Value=60&Attribute.2.Name=DelaySeconds&Attribute.2.Value=5",
+        );
+        let response = process(app_state.clone(), &set, "/test", false).await;
+        assert_eq!(response.status(), 200);
+
+        let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+        let queue = service.get_queue("test").await.unwrap().unwrap();
+        let expected = HashMap::from([
+            ("VisibilityTimeout".to_string(), "60".to_string()),
+            ("DelaySeconds".to_string(), "5".to_string()),
+        ]);
+        assert_eq!(
+            service.get_attributes(queue.id.unwrap()).await.unwrap(),
+            expected
+        );
+        let queues = app_state.queues.lock().await;
+        assert_eq!(
+            queues.get("test").unwrap().conflicting_attribute(&expected),
+            None
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tracing::error;
+
+use super::{error::SqsError, helpers};
+use crate::AppState;
+
+/// A queue can have at most 50 tags
+const MAX_TAGS: usize = 50;
+const MAX_TAG_KEY_LENGTH: usize = 128;
+const MAX_TAG_VALUE_LENGTH: usize = 256;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TagQueueParams {
+    queue_url: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct TagQueueResponse {
+    response_metadata: ResponseMetadata,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResponseMetadata {
+    request_id: String,
+}
+
+/// Read the `Tag.N.Key` and `Tag.N.Value` parameters
+pub(super) fn extract_tags(extra: &HashMap<String, String>) -> HashMap<String, String> {
+    helpers::extract_entries("Tag", extra)
+        .into_iter()
+        .map(|entry| {
+            (
+                entry.get("Key").cloned().unwrap_or_default(),
+                entry.get("Value").cloned().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+/// Check the tag lengths and that the queue stays within the tag limit
+pub(super) fn validate_tags(
+    tags: &HashMap<String, String>,
+    existing: &HashMap<String, String>,
+) -> Result<(), SqsError> {
+    for (key, value) in tags {
+        if key.is_empty() || key.chars().count() > MAX_TAG_KEY_LENGTH {
+            return Err(SqsError::invalid_parameter_value(&format!(
+                "Tag keys must be between 1 and {} characters long.",
+                MAX_TAG_KEY_LENGTH
+            )));
+        }
+        if key.starts_with("aws:") {
+            return Err(SqsError::invalid_parameter_value(
+                "Tag keys must not start with the reserved prefix aws:.",
+            ));
+        }
+        if value.chars().count() > MAX_TAG_VALUE_LENGTH {
+            return Err(SqsError::invalid_parameter_value(&format!(
+                "Tag values can be at most {} characters long.",
+                MAX_TAG_VALUE_LENGTH
+            )));
+        }
+    }
+
+    let added = tags
+        .keys()
+        .filter(|key| !existing.contains_key(*key))
+        .count();
+    if existing.len() + added > MAX_TAGS {
+        return Err(SqsError::invalid_parameter_value(&format!(
+            "Too many tags added for queue. A queue can have at most {} tags.",
+            MAX_TAGS
+        )));
+    }
+
+    Ok(())
+}
+
+/// Add or overwrite tags of a queue
+pub async fn process(
+    app_state: Arc<AppState>,
+    payload: &web::Bytes,
+    queue_path: &str,
+    _is_json: bool,
+) -> HttpResponse {
+    let payload = match super::struct_from_url_encode::<TagQueueParams>(payload) {
+        Ok(p) => p,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Failed to parse payload: {}", e))
+        }
+    };
+
+    let tags = extract_tags(&payload.extra);
+    if tags.is_empty() {
+        return SqsError::missing_parameter("Tag.1.Key").response();
+    }
+
+    let queue_name = match super::target_queue_name(payload.queue_url.as_deref(), queue_path) {
+        Ok(name) => name,
+        Err(e) => return e.response(),
+    };
+
+    let service = crate::service::queue::Queue::new(&app_state.db_pool, &app_state.host_name);
+    let queue = match service.get_queue(&queue_name).await {
+        Ok(Some(queue)) => queue,
+        Ok(None) => return SqsError::non_existent_queue().response(),
+        Err(e) => {
+            error!("Failed to get queue: {}", e);
+            return HttpResponse::InternalServerError().body(format!("Failed to get queue: {}", e));
+        }
+    };
+    let queue_id = queue.id.unwrap_or_default();
+
+    let existing = match service.get_tags(queue_id).await {
+        Ok(existing) => existing,
+        Err(e) => {
+            error!("Failed to get queue tags: {}", e);
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to get queue tags: {}", e));
+        }
+    };
+    if let Err(e) = validate_tags(&tags, &existing) {
+        return e.response();
+    }
+
+    if let Err(e) = service.create_tags(queue_id, tags).await {
+        error!("Failed to tag queue: {}", e);
+        return HttpResponse::InternalServerError().body(format!("Failed to tag queue: {}", e));
+    }
+
+    let response = TagQueueResponse {
+        response_metadata: ResponseMetadata {
+            request_id: helpers::generate_random_uuid4(),
+        },
+    };
+
+    match quick_xml::se::to_string(&response) {
+        Ok(resp) => HttpResponse::Ok().body(resp),
+        Err(e) => {
+            HttpResponse::InternalServerError().body(format!("Failed to serialize response: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_tags() {
+        let mut extra = HashMap::new();
+        extra.insert("Tag.1.Key".to_string(), "Team".to_string());
+        extra.insert("Tag.1.Value".to_string(), "payments".to_string());
+        extra.insert("Tag.2.Key".to_string(), "team".to_string());
+        extra.insert("Tag.2.Value".to_string(), "billing".to_string());
+
+        let tags = extract_tags(&extra);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags.get("Team"), Some(&"payments".to_string()));
+        assert_eq!(tags.get("team"), Some(&"billing".to_string()));
+    }
+
+    #[test]
+    fn test_validate_tags() {
+        let mut tags = HashMap::new();
+        tags.insert("Team".to_string(), "payments".to_string());
+        assert!(validate_tags(&tags, &HashMap::new()).is_ok());
+
+        let existing: HashMap<String, String> = (0..MAX_TAGS)
+            .map(|i| (format!("key{}", i), "value".to_string()))
+            .collect();
+        assert!(validate_tags(&tags, &existing).is_err());
+
+        let mut overwrite = HashMap::new();
+        overwrite.insert("key0".to_string(), "other".to_string());
+        assert!(validate_tags(&overwrite, &existing).is_ok());
+
+        let mut long_key = HashMap::new();
+        long_key.insert("k".repeat(MAX_TAG_KEY_LENGTH + 1), "value".to_string());
+        assert!(validate_tags(&long_key, &HashMap::new()).is_err());
+
+        let mut long_value = HashMap::new();
+        long_value.insert("key".to_string(), "v".repeat(MAX_TAG_VALUE_LENGTH + 1));
+        assert!(validate_tags(&long_value, &HashMap::new()).is_err());
+    }
+}
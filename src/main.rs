@@ -9,6 +9,10 @@ mod api;
 mod queue;
 mod service;
 
+/// Largest request body accepted. URL encoding can triple the size of a 1 MiB message
+/// or batch, the rest leaves room for the parameter names.
+const MAX_PAYLOAD_SIZE: usize = 4 * queue::MAX_MESSAGE_SIZE as usize;
+
 #[derive(clap::Parser, Debug)]
 #[command(author, about, version)]
 struct CliParams {
@@ -20,12 +24,17 @@ struct CliParams {
     db_url: String,
     #[clap(long, default_value = "http://locahost:9090")]
     host_name: String,
+    /// Throttle FIFO sends above the AWS throughput quotas
+    #[clap(long)]
+    enforce_quotas: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub db_pool: SqlitePool,
     pub host_name: String,
+    /// Reject FIFO sends above the per queue or per message group quota
+    pub enforce_quotas: bool,
     pub queues: Arc<Mutex<HashMap<String, queue::Queue>>>,
 }
 
@@ -48,19 +57,41 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    let queue_list: HashMap<String, queue::Queue> = HashMap::new();
+    info!("Loading queues ...");
+    let service = service::queue::Queue::new(&db_pool, &cli_params.host_name);
+    let entities = match service.list_queue_entities().await {
+        Ok(entities) => entities,
+        Err(e) => {
+            error!("Failed to load queues: {}", e);
+            return Err(anyhow::anyhow!("Failed to load queues"));
+        }
+    };
+
+    let mut queue_list: HashMap<String, queue::Queue> = HashMap::new();
+    for entity in entities {
+        let mut queue = queue::Queue::new();
+        queue.set_attributes(entity.attributes.unwrap_or_default());
+        queue.set_sequence_number(entity.sequence_number.unwrap_or_default());
+        queue_list.insert(entity.name, queue);
+    }
+
     let state = AppState {
         db_pool,
         host_name: cli_params.host_name,
+        enforce_quotas: cli_params.enforce_quotas,
         queues: Arc::new(Mutex::new(queue_list)),
     };
 
+    info!("Starting retention sweeper ...");
+    tokio::spawn(drop_expired_messages(state.queues.clone()));
+
     info!("Starting server ...");
     HttpServer::new(move || {
         App::new()
             .service(index)
             .service(api::post_handler)
             .app_data(web::Data::new(state.clone()))
+            .app_data(web::PayloadConfig::new(MAX_PAYLOAD_SIZE))
             .wrap(middleware::Logger::default())
     })
     .bind((cli_params.bind_address, cli_params.port))?
@@ -71,6 +102,23 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Drop messages older than their queue's MessageRetentionPeriod, runs for the life of the server
+async fn drop_expired_messages(queues: Arc<Mutex<HashMap<String, queue::Queue>>>) {
+    let mut interval = tokio::time::interval(queue::RETENTION_SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let now = std::time::Instant::now();
+        let mut queues = queues.lock().await;
+        for (name, queue) in queues.iter_mut() {
+            let dropped = queue.drop_expired(now);
+            if dropped > 0 {
+                info!("Dropped {} expired messages from queue {}", dropped, name);
+            }
+        }
+    }
+}
+
 #[get("/")]
 async fn index() -> HttpResponse {
     HttpResponse::Ok().body("Hello world!")
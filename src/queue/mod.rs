@@ -1,37 +1,409 @@
-use std::{collections::VecDeque, sync::{Arc, Mutex}};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Notify;
+
+/// Longest time a message can stay hidden after a receive, 12 hours
+pub const MAX_VISIBILITY_TIMEOUT: u64 = 43_200;
+
+/// Largest MaximumMessageSize a queue can be configured with, 1 MiB
+pub const MAX_MESSAGE_SIZE: u64 = 1_048_576;
+
+/// Longest delay of a message before it can be received, 15 minutes
+pub const MAX_DELAY_SECONDS: u64 = 900;
+
+/// Longest time a receive can wait for messages to arrive
+pub const MAX_WAIT_TIME_SECONDS: u64 = 20;
+
+/// A FIFO queue drops sends with an already seen deduplication id for this long
+pub const DEDUPLICATION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Sends a FIFO queue accepts per second, per queue or per message group in high throughput
+/// mode. A SendMessageBatch call counts once towards each quota its messages use.
+pub const MAX_FIFO_SENDS_PER_SECOND: u32 = 300;
+
+/// Sequence numbers a FIFO queue reserves in the database at once, a restart skips
+/// whatever is left of the block
+pub const SEQUENCE_NUMBER_BLOCK: i64 = 1000;
+
+/// How often messages past the retention period are dropped
+pub const RETENTION_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A queue can only be purged once in this period
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Values of the queue attributes which were not set when the queue was created
+pub const DEFAULT_ATTRIBUTES: [(&str, &str); 5] = [
+    ("DelaySeconds", "0"),
+    ("MaximumMessageSize", "262144"),
+    ("MessageRetentionPeriod", "345600"),
+    ("ReceiveMessageWaitTimeSeconds", "0"),
+    ("VisibilityTimeout", "30"),
+];
+
+/// Values of the FIFO only attributes which were not set when the queue was created
+pub const DEFAULT_FIFO_ATTRIBUTES: [(&str, &str); 3] = [
+    ("ContentBasedDeduplication", "false"),
+    ("DeduplicationScope", "queue"),
+    ("FifoThroughputLimit", "perQueue"),
+];
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Message {
     pub id: String,
-    pub message_body: String
+    pub message_body: String,
+    /// How many times the message was handed out by a receive
+    pub receive_count: u32,
+    /// When the message was sent, the retention period counts from here
+    pub sent_at: Instant,
+    /// Milliseconds since the epoch when the message was sent
+    pub sent_timestamp: u128,
+    /// Milliseconds since the epoch when the message was received for the first time
+    pub first_receive_timestamp: Option<u128>,
+    pub message_group_id: Option<String>,
+    pub message_deduplication_id: Option<String>,
+    pub sequence_number: Option<String>,
+    /// Attributes set by the sender, keyed and sorted by name
+    pub message_attributes: BTreeMap<String, MessageAttribute>,
+    /// System attributes set by the sender, i.e. `AWSTraceHeader`
+    pub system_attributes: BTreeMap<String, MessageAttribute>,
+}
+
+/// A typed attribute of a message. The data type is String, Number or Binary,
+/// optionally followed by a custom `.label`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageAttribute {
+    pub data_type: String,
+    pub string_value: Option<String>,
+    pub binary_value: Option<Vec<u8>>,
+}
+
+impl Message {
+    pub fn new(id: &str, message_body: &str) -> Message {
+        Message {
+            id: id.to_owned(),
+            message_body: message_body.to_owned(),
+            receive_count: 0,
+            sent_at: Instant::now(),
+            sent_timestamp: epoch_millis(),
+            first_receive_timestamp: None,
+            message_group_id: None,
+            message_deduplication_id: None,
+            sequence_number: None,
+            message_attributes: BTreeMap::new(),
+            system_attributes: BTreeMap::new(),
+        }
+    }
+}
+
+/// A message which was handed out by a receive and is waiting to be deleted.
+#[derive(Debug, Clone, PartialEq)]
+struct InFlightMessage {
+    receipt_handle: String,
+    message: Message,
+    /// When the message becomes visible again unless it is deleted before
+    visible_at: Instant,
+}
+
+impl InFlightMessage {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.visible_at <= now
+    }
 }
 
+/// A message which was sent with a delay and can't be received yet.
 #[derive(Debug, Clone, PartialEq)]
-pub struct QueueTags {
-    key: String,
-    value: String,
+struct DelayedMessage {
+    message: Message,
+    visible_at: Instant,
+}
+
+/// The message a deduplication id was first seen with
+#[derive(Debug, Clone, PartialEq)]
+struct DeduplicationEntry {
+    message_id: String,
+    sequence_number: Option<String>,
+    expires_at: Instant,
+}
+
+/// Sends counted towards a throughput quota in the current one second window
+#[derive(Debug, Clone, PartialEq)]
+struct ThroughputWindow {
+    started_at: Instant,
+    sends: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReceiptError {
+    /// The handle is malformed or does not belong to the latest receive
+    Invalid,
+    /// The message is known but is not in flight anymore
+    NotInFlight,
 }
 
 #[derive(Debug, Clone)]
 pub struct Queue {
-    name: String,
-    tags: Vec<QueueTags>,
+    /// Current attribute values, set on creation and by SetQueueAttributes
+    attributes: HashMap<String, String>,
 
     messages: VecDeque<Message>,
+    /// Messages waiting for their delay to expire, in send order
+    delayed: Vec<DelayedMessage>,
+    /// Received messages keyed by message id
+    in_flight: HashMap<String, InFlightMessage>,
+    last_purged_at: Option<Instant>,
+    /// Deduplication ids of FIFO sends within the deduplication interval, keyed by message
+    /// group as well when deduplication is scoped to message groups
+    deduplication: HashMap<(Option<String>, String), DeduplicationEntry>,
+    /// Send quota windows, keyed by message group when the throughput limit is per group
+    throughput: HashMap<Option<String>, ThroughputWindow>,
+    /// Last sequence number handed out to a FIFO message
+    sequence_number: i64,
+    /// Sequence numbers up to this one are persisted and can be handed out
+    reserved_sequence_number: i64,
+
+    /// Wakes up long polling receives when a message becomes available
+    notify: Arc<Notify>,
 }
 
 /// Queue is a FIFO data structure to implement a mock SQS queue.
 impl Queue {
-    pub fn new(name: &str, tags: Vec<QueueTags>) -> Queue {
+    pub fn new() -> Queue {
         Queue {
-            name: name.to_owned(),
-            tags,
+            attributes: HashMap::new(),
             messages: VecDeque::new(),
+            delayed: Vec::new(),
+            in_flight: HashMap::new(),
+            last_purged_at: None,
+            deduplication: HashMap::new(),
+            throughput: HashMap::new(),
+            sequence_number: 0,
+            reserved_sequence_number: 0,
+            notify: Arc::new(Notify::new()),
         }
     }
 
+    /// Update attribute values, they apply to every following send and receive
+    pub fn set_attributes(&mut self, attributes: HashMap<String, String>) {
+        self.attributes.extend(attributes);
+    }
+
+    /// Current value of an attribute, falling back to its default
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .get(name)
+            .map(|value| value.as_str())
+            .or_else(|| {
+                DEFAULT_ATTRIBUTES
+                    .iter()
+                    .chain(DEFAULT_FIFO_ATTRIBUTES.iter().filter(|_| self.is_fifo()))
+                    .find(|(default, _)| *default == name)
+                    .map(|(_, value)| *value)
+            })
+    }
+
+    /// Name of an attribute whose given value differs from the current one, if any
+    pub fn conflicting_attribute<'a>(
+        &self,
+        attributes: &'a HashMap<String, String>,
+    ) -> Option<&'a str> {
+        attributes
+            .iter()
+            .filter(|(name, value)| self.attribute(name) != Some(value.as_str()))
+            .map(|(name, _)| name.as_str())
+            .min()
+    }
+
+    /// FIFO queues are created with the `FifoQueue` attribute set to true
+    pub fn is_fifo(&self) -> bool {
+        self.attributes
+            .get("FifoQueue")
+            .is_some_and(|value| value == "true")
+    }
+
+    /// Whether a FIFO queue derives missing deduplication ids from the message body
+    pub fn content_based_deduplication(&self) -> bool {
+        self.attribute("ContentBasedDeduplication") == Some("true")
+    }
+
+    /// Whether deduplication ids are tracked per message group instead of per queue
+    pub fn deduplication_per_message_group(&self) -> bool {
+        self.attribute("DeduplicationScope") == Some("messageGroup")
+    }
+
+    /// Whether the send quota applies to each message group instead of the whole queue
+    pub fn throughput_per_message_group(&self) -> bool {
+        self.attribute("FifoThroughputLimit") == Some("perMessageGroupId")
+    }
+
+    /// How long a received message stays hidden unless the receive asks otherwise
+    pub fn visibility_timeout(&self) -> Duration {
+        let seconds = self
+            .attribute("VisibilityTimeout")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(30);
+        Duration::from_secs(seconds)
+    }
+
+    /// How long a new message is delayed unless the send asks otherwise
+    pub fn delay(&self) -> Duration {
+        let seconds = self
+            .attribute("DelaySeconds")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        Duration::from_secs(seconds)
+    }
+
+    /// Largest message body plus attributes accepted by a send, in bytes
+    pub fn maximum_message_size(&self) -> usize {
+        self.attribute("MaximumMessageSize")
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(262_144)
+    }
+
+    /// How long a message is kept before it is dropped, whether it was received or not
+    pub fn retention_period(&self) -> Duration {
+        let seconds = self
+            .attribute("MessageRetentionPeriod")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(345_600);
+        Duration::from_secs(seconds)
+    }
+
+    /// How long a receive waits for messages unless the receive asks otherwise
+    pub fn receive_wait_time(&self) -> Duration {
+        let seconds = self
+            .attribute("ReceiveMessageWaitTimeSeconds")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        Duration::from_secs(seconds)
+    }
+
+    /// Notified whenever a message is pushed or returned to the queue
+    pub fn notifier(&self) -> Arc<Notify> {
+        self.notify.clone()
+    }
+
+    /// Earliest time an in-flight or delayed message becomes visible
+    pub fn next_visible_at(&self) -> Option<Instant> {
+        self.in_flight
+            .values()
+            .map(|in_flight| in_flight.visible_at)
+            .chain(self.delayed.iter().map(|delayed| delayed.visible_at))
+            .min()
+    }
+
     pub fn push(&mut self, msg: Message) {
         self.messages.push_back(msg);
+        self.notify.notify_waiters();
+    }
+
+    fn deduplication_key(
+        &self,
+        message_group_id: Option<&str>,
+        deduplication_id: &str,
+    ) -> (Option<String>, String) {
+        let group = message_group_id
+            .filter(|_| self.deduplication_per_message_group())
+            .map(|group| group.to_owned());
+        (group, deduplication_id.to_owned())
+    }
+
+    /// Id and sequence number of the message sent with the same deduplication id
+    /// within the deduplication interval
+    pub fn find_duplicate(
+        &mut self,
+        message_group_id: Option<&str>,
+        deduplication_id: &str,
+    ) -> Option<(String, Option<String>)> {
+        let now = Instant::now();
+        self.deduplication.retain(|_, entry| entry.expires_at > now);
+
+        self.deduplication
+            .get(&self.deduplication_key(message_group_id, deduplication_id))
+            .map(|entry| (entry.message_id.clone(), entry.sequence_number.clone()))
+    }
+
+    /// Remember a deduplication id, later sends with it are dropped for the deduplication interval
+    pub fn record_deduplication(&mut self, deduplication_id: &str, message: &Message) {
+        self.deduplication.insert(
+            self.deduplication_key(message.message_group_id.as_deref(), deduplication_id),
+            DeduplicationEntry {
+                message_id: message.id.clone(),
+                sequence_number: message.sequence_number.clone(),
+                expires_at: Instant::now() + DEDUPLICATION_INTERVAL,
+            },
+        );
+    }
+
+    /// Quota a send counts towards, its message group's own when the throughput limit is
+    /// per message group and the queue's otherwise
+    pub fn throughput_key(&self, message_group_id: Option<&str>) -> Option<String> {
+        message_group_id
+            .filter(|_| self.throughput_per_message_group())
+            .map(|group| group.to_owned())
+    }
+
+    /// Count a send towards a throughput quota, false when the quota is used up for the
+    /// current second
+    pub fn acquire_send(&mut self, key: &Option<String>, now: Instant) -> bool {
+        self.throughput
+            .retain(|_, window| now.duration_since(window.started_at) < Duration::from_secs(1));
+        let window = self
+            .throughput
+            .entry(key.clone())
+            .or_insert(ThroughputWindow {
+                started_at: now,
+                sends: 0,
+            });
+        if window.sends >= MAX_FIFO_SENDS_PER_SECOND {
+            return false;
+        }
+
+        window.sends += 1;
+        true
+    }
+
+    /// Last sequence number reserved in the database, numbers keep increasing across restarts
+    /// by continuing after it
+    pub fn reserved_sequence_number(&self) -> i64 {
+        self.reserved_sequence_number
+    }
+
+    /// Continue after a sequence number loaded from the database
+    pub fn set_sequence_number(&mut self, sequence_number: i64) {
+        self.sequence_number = self.sequence_number.max(sequence_number);
+        self.reserve_sequence_numbers(sequence_number);
+    }
+
+    /// Sequence numbers which can be handed out before more have to be reserved
+    pub fn sequence_numbers_left(&self) -> i64 {
+        self.reserved_sequence_number - self.sequence_number
+    }
+
+    /// Record that sequence numbers up to `sequence_number` are persisted
+    pub fn reserve_sequence_numbers(&mut self, sequence_number: i64) {
+        self.reserved_sequence_number = self.reserved_sequence_number.max(sequence_number);
+    }
+
+    /// Hand out the next sequence number, formatted as the 20 digit string SQS uses
+    pub fn next_sequence_number(&mut self) -> String {
+        self.sequence_number += 1;
+        format!("{:020}", self.sequence_number)
+    }
+
+    /// Add a message which can't be received before `delay` has passed
+    pub fn push_delayed(&mut self, msg: Message, delay: Duration) {
+        if delay.is_zero() {
+            return self.push(msg);
+        }
+
+        self.delayed.push(DelayedMessage {
+            message: msg,
+            visible_at: Instant::now() + delay,
+        });
     }
 
     /// Remove a message from the queue by id.
@@ -40,11 +412,255 @@ impl Queue {
             .iter()
             .position(|message| message.id == id)
             .map(|index| self.messages.remove(index));
+        self.delayed.retain(|delayed| delayed.message.id != id);
+        self.in_flight.remove(id);
     }
 
     pub fn pop(&mut self) -> Option<Message> {
         self.messages.pop_front()
     }
+
+    /// Take the next message and hide it for `visibility_timeout`, or the queue's
+    /// VisibilityTimeout when `None`. The message comes back if it is not deleted in time.
+    /// Returns the message together with a receipt handle unique to this receive.
+    pub fn receive(&mut self, visibility_timeout: Option<Duration>) -> Option<(String, Message)> {
+        self.receive_batch(1, visibility_timeout).pop()
+    }
+
+    /// Same as `receive` for up to `max` messages, a message is handed out at most once per call
+    pub fn receive_batch(
+        &mut self,
+        max: usize,
+        visibility_timeout: Option<Duration>,
+    ) -> Vec<(String, Message)> {
+        let now = Instant::now();
+        self.release_expired(now);
+
+        // On FIFO queues a message group stays locked while any of its messages is in flight
+        let locked_groups: HashSet<String> = if self.is_fifo() {
+            self.in_flight
+                .values()
+                .filter_map(|in_flight| in_flight.message.message_group_id.clone())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let timeout = visibility_timeout.unwrap_or_else(|| self.visibility_timeout());
+        let mut received = Vec::new();
+        while received.len() < max {
+            let index = self.messages.iter().position(|message| {
+                message
+                    .message_group_id
+                    .as_ref()
+                    .is_none_or(|group| !locked_groups.contains(group))
+            });
+            let mut message = match index.and_then(|index| self.messages.remove(index)) {
+                Some(message) => message,
+                None => break,
+            };
+            message.receive_count += 1;
+            message
+                .first_receive_timestamp
+                .get_or_insert_with(epoch_millis);
+
+            let receipt_handle = new_receipt_handle(&message.id);
+            self.in_flight.insert(
+                message.id.clone(),
+                InFlightMessage {
+                    receipt_handle: receipt_handle.clone(),
+                    message: message.clone(),
+                    visible_at: now + timeout,
+                },
+            );
+            received.push((receipt_handle, message));
+        }
+
+        received
+    }
+
+    /// Delete an in-flight message by the receipt handle of its latest receive.
+    /// Any handle that does not belong to an in-flight message is invalid for a delete,
+    /// including one of a message that is visible again.
+    pub fn delete_message(&mut self, receipt_handle: &str) -> Result<(), ReceiptError> {
+        let id = self
+            .in_flight_id(receipt_handle)
+            .map_err(|_| ReceiptError::Invalid)?;
+        self.remove(&id);
+        // Deleting may unlock a FIFO message group
+        self.notify.notify_waiters();
+
+        Ok(())
+    }
+
+    /// Hide an in-flight message for `timeout` counted from now.
+    /// A zero timeout makes the message visible again right away.
+    pub fn change_visibility(
+        &mut self,
+        receipt_handle: &str,
+        timeout: Duration,
+    ) -> Result<(), ReceiptError> {
+        let id = self.in_flight_id(receipt_handle)?;
+
+        if timeout.is_zero() {
+            if let Some(in_flight) = self.in_flight.remove(&id) {
+                self.requeue(in_flight.message);
+            }
+        } else if let Some(in_flight) = self.in_flight.get_mut(&id) {
+            in_flight.visible_at = Instant::now() + timeout;
+        }
+        // Long polls sleep until the earliest visible_at, which may have moved earlier
+        self.notify.notify_waiters();
+
+        Ok(())
+    }
+
+    /// Drop every message of the queue, whether it is visible or in flight.
+    /// Returns false without touching the queue when it was purged less than 60 seconds ago.
+    pub fn purge(&mut self) -> bool {
+        let now = Instant::now();
+        if self
+            .last_purged_at
+            .is_some_and(|at| now.duration_since(at) < PURGE_INTERVAL)
+        {
+            return false;
+        }
+
+        self.messages.clear();
+        self.delayed.clear();
+        self.in_flight.clear();
+        self.last_purged_at = Some(now);
+
+        true
+    }
+
+    /// Drop every message which was sent longer than the retention period ago.
+    /// Returns the number of dropped messages.
+    pub fn drop_expired(&mut self, now: Instant) -> usize {
+        let retention_period = self.retention_period();
+        let is_alive = |message: &Message| now.duration_since(message.sent_at) < retention_period;
+
+        let before = self.messages.len() + self.delayed.len() + self.in_flight.len();
+        self.messages.retain(|message| is_alive(message));
+        self.delayed.retain(|delayed| is_alive(&delayed.message));
+        self.in_flight
+            .retain(|_, in_flight| is_alive(&in_flight.message));
+
+        before - (self.messages.len() + self.delayed.len() + self.in_flight.len())
+    }
+
+    /// Number of messages available for receive
+    pub fn approximate_number_of_messages(&self) -> usize {
+        let now = Instant::now();
+        self.messages.len()
+            + self
+                .in_flight
+                .values()
+                .filter(|in_flight| in_flight.is_expired(now))
+                .count()
+            + self
+                .delayed
+                .iter()
+                .filter(|delayed| delayed.visible_at <= now)
+                .count()
+    }
+
+    /// Number of messages which were received but not deleted yet
+    pub fn approximate_number_of_messages_not_visible(&self) -> usize {
+        let now = Instant::now();
+        self.in_flight
+            .values()
+            .filter(|in_flight| !in_flight.is_expired(now))
+            .count()
+    }
+
+    /// Number of messages which wait for their delay to expire
+    pub fn approximate_number_of_messages_delayed(&self) -> usize {
+        let now = Instant::now();
+        self.delayed
+            .iter()
+            .filter(|delayed| delayed.visible_at > now)
+            .count()
+    }
+
+    /// Resolve the message id of an in-flight message from its current receipt handle
+    fn in_flight_id(&self, receipt_handle: &str) -> Result<String, ReceiptError> {
+        let id = message_id_from_receipt_handle(receipt_handle).ok_or(ReceiptError::Invalid)?;
+
+        match self.in_flight.get(&id) {
+            Some(in_flight) if in_flight.receipt_handle == receipt_handle => Ok(id),
+            Some(_) => Err(ReceiptError::Invalid),
+            None if self.messages.iter().any(|message| message.id == id) => {
+                Err(ReceiptError::NotInFlight)
+            }
+            None => Err(ReceiptError::Invalid),
+        }
+    }
+
+    /// Return in-flight messages whose visibility timeout ran out to the queue
+    /// and make delayed messages whose delay passed available
+    fn release_expired(&mut self, now: Instant) {
+        let (due, delayed) = std::mem::take(&mut self.delayed)
+            .into_iter()
+            .partition(|delayed| delayed.visible_at <= now);
+        self.delayed = delayed;
+        self.messages.extend(
+            due.into_iter()
+                .map(|delayed: DelayedMessage| delayed.message),
+        );
+
+        let expired: Vec<String> = self
+            .in_flight
+            .iter()
+            .filter(|(_, in_flight)| in_flight.is_expired(now))
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in expired {
+            if let Some(in_flight) = self.in_flight.remove(&id) {
+                self.requeue(in_flight.message);
+            }
+        }
+    }
+
+    /// Put a message which was in flight back in send order, ahead of every later message
+    fn requeue(&mut self, message: Message) {
+        let index = self
+            .messages
+            .partition_point(|queued| queued.sent_at <= message.sent_at);
+        self.messages.insert(index, message);
+    }
+}
+
+fn epoch_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis())
+        .unwrap_or_default()
+}
+
+/// Receipt handles are the hex encoded `<message id>#<nonce>` pair, so that every
+/// receive of the same message gets a different handle.
+fn new_receipt_handle(message_id: &str) -> String {
+    format!("{}#{}", message_id, uuid::Uuid::new_v4().simple())
+        .bytes()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn message_id_from_receipt_handle(receipt_handle: &str) -> Option<String> {
+    if !receipt_handle.len().is_multiple_of(2) || !receipt_handle.is_ascii() {
+        return None;
+    }
+
+    let bytes = (0..receipt_handle.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&receipt_handle[i..i + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    let decoded = String::from_utf8(bytes).ok()?;
+    let (id, _nonce) = decoded.rsplit_once('#')?;
+
+    Some(id.to_owned())
 }
 
 #[cfg(test)]
@@ -53,23 +669,39 @@ mod test {
 
     #[test]
     fn test_push() {
-        let mut queue = Queue::new("test", vec![]);
-        let message = Message {
-            id: "id".to_owned(),
-            message_body: "message_body".to_owned()
-        };
+        let mut queue = Queue::new();
+        let message = Message::new("id", "message_body");
         queue.push(message.clone());
         assert_eq!(queue.messages.len(), 1);
         assert_eq!(queue.messages[0].id, "id");
     }
 
+    #[test]
+    fn test_conflicting_attribute() {
+        let mut queue = Queue::new();
+        queue.set_attributes(HashMap::from([(
+            "VisibilityTimeout".to_string(),
+            "60".to_string(),
+        )]));
+
+        let same = HashMap::from([
+            ("VisibilityTimeout".to_string(), "60".to_string()),
+            ("DelaySeconds".to_string(), "0".to_string()),
+        ]);
+        assert_eq!(queue.conflicting_attribute(&same), None);
+        assert_eq!(queue.conflicting_attribute(&HashMap::new()), None);
+
+        let different = HashMap::from([
+            ("VisibilityTimeout".to_string(), "30".to_string()),
+            ("DelaySeconds".to_string(), "5".to_string()),
+        ]);
+        assert_eq!(queue.conflicting_attribute(&different), Some("DelaySeconds"));
+    }
+
     #[test]
     fn test_remove() {
-        let mut queue = Queue::new("test", vec![]);
-        let message = Message {
-            id: "id".to_owned(),
-            message_body: "message_body".to_owned()
-        };
+        let mut queue = Queue::new();
+        let message = Message::new("id", "message_body");
         queue.push(message.clone());
         queue.remove("id");
         assert_eq!(queue.messages.len(), 0);
@@ -77,14 +709,395 @@ mod test {
 
     #[test]
     fn test_pop() {
-        let mut queue = Queue::new("test", vec![]);
-        let message = Message {
-            id: "id".to_owned(),
-            message_body: "message_body".to_owned()
-        };
+        let mut queue = Queue::new();
+        let message = Message::new("id", "message_body");
         queue.push(message.clone());
         let popped = queue.pop();
         assert_eq!(&popped, &Some(message));
         assert_eq!(queue.messages.len(), 0);
     }
+
+    #[test]
+    fn test_receive_and_delete() {
+        let mut queue = Queue::new();
+        let message = Message::new("id", "message_body");
+        queue.push(message.clone());
+
+        let (receipt_handle, received) = queue.receive(None).unwrap();
+        assert_eq!(received.id, message.id);
+        assert_eq!(received.receive_count, 1);
+        assert_eq!(queue.in_flight.len(), 1);
+        assert_eq!(
+            message_id_from_receipt_handle(&receipt_handle),
+            Some("id".to_owned())
+        );
+
+        assert_eq!(
+            queue.delete_message("not-a-handle"),
+            Err(ReceiptError::Invalid)
+        );
+        assert_eq!(queue.delete_message(&receipt_handle), Ok(()));
+        assert!(queue.in_flight.is_empty());
+        assert_eq!(
+            queue.delete_message(&receipt_handle),
+            Err(ReceiptError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_delete_visible_again() {
+        let mut queue = Queue::new();
+        queue.push(Message::new("id", "message_body"));
+
+        let (receipt_handle, _) = queue.receive(None).unwrap();
+        assert_eq!(
+            queue.change_visibility(&receipt_handle, Duration::ZERO),
+            Ok(())
+        );
+        assert_eq!(
+            queue.delete_message(&receipt_handle),
+            Err(ReceiptError::Invalid)
+        );
+        assert_eq!(queue.approximate_number_of_messages(), 1);
+    }
+
+    #[test]
+    fn test_change_visibility() {
+        let mut queue = Queue::new();
+        let message = Message::new("id", "message_body");
+        queue.push(message.clone());
+
+        let (receipt_handle, _) = queue.receive(None).unwrap();
+        assert_eq!(
+            queue.change_visibility(&receipt_handle, Duration::from_secs(60)),
+            Ok(())
+        );
+        assert!(queue.receive(None).is_none());
+
+        assert_eq!(
+            queue.change_visibility(&receipt_handle, Duration::ZERO),
+            Ok(())
+        );
+        assert_eq!(
+            queue.change_visibility(&receipt_handle, Duration::ZERO),
+            Err(ReceiptError::NotInFlight)
+        );
+
+        let (new_handle, received) = queue.receive(None).unwrap();
+        assert_eq!(received.id, message.id);
+        assert_eq!(received.receive_count, 2);
+        assert_eq!(
+            queue.change_visibility(&receipt_handle, Duration::ZERO),
+            Err(ReceiptError::Invalid)
+        );
+        assert_eq!(queue.delete_message(&new_handle), Ok(()));
+    }
+
+    #[test]
+    fn test_approximate_number_of_messages() {
+        let mut queue = Queue::new();
+        for id in ["one", "two"] {
+            queue.push(Message::new(id, "message_body"));
+        }
+
+        let (receipt_handle, _) = queue.receive(None).unwrap();
+        assert_eq!(queue.approximate_number_of_messages(), 1);
+        assert_eq!(queue.approximate_number_of_messages_not_visible(), 1);
+
+        queue
+            .change_visibility(&receipt_handle, Duration::ZERO)
+            .unwrap();
+        assert_eq!(queue.approximate_number_of_messages(), 2);
+        assert_eq!(queue.approximate_number_of_messages_not_visible(), 0);
+    }
+
+    #[test]
+    fn test_purge() {
+        let mut queue = Queue::new();
+        for id in ["one", "two"] {
+            queue.push(Message::new(id, "message_body"));
+        }
+        queue.receive(None).unwrap();
+
+        assert!(queue.purge());
+        assert!(queue.messages.is_empty());
+        assert!(queue.in_flight.is_empty());
+
+        queue.push(Message::new("three", "message_body"));
+        assert!(!queue.purge());
+        assert_eq!(queue.messages.len(), 1);
+
+        queue.last_purged_at = Some(Instant::now() - PURGE_INTERVAL);
+        assert!(queue.purge());
+        assert!(queue.messages.is_empty());
+    }
+
+    #[test]
+    fn test_release_expired() {
+        let mut queue = Queue::new();
+        let message = Message::new("id", "message_body");
+        queue.push(message.clone());
+
+        let (receipt_handle, _) = queue.receive(None).unwrap();
+        queue
+            .change_visibility(&receipt_handle, Duration::from_secs(30))
+            .unwrap();
+
+        queue.release_expired(Instant::now());
+        assert!(queue.messages.is_empty());
+        queue.release_expired(Instant::now() + Duration::from_secs(31));
+        assert_eq!(queue.messages.front().map(|m| &m.id), Some(&message.id));
+        assert!(queue.in_flight.is_empty());
+    }
+
+    #[test]
+    fn test_visibility_timeout() {
+        let mut queue = Queue::new();
+        queue.push(Message::new("id", "message_body"));
+        assert_eq!(queue.visibility_timeout(), Duration::from_secs(30));
+
+        let (_, received) = queue.receive(Some(Duration::ZERO)).unwrap();
+        assert_eq!(received.receive_count, 1);
+        let first_receive_timestamp = received.first_receive_timestamp;
+        assert!(first_receive_timestamp >= Some(received.sent_timestamp));
+        let (_, received) = queue.receive(None).unwrap();
+        assert_eq!(received.receive_count, 2);
+        assert_eq!(received.first_receive_timestamp, first_receive_timestamp);
+        assert!(queue.receive(None).is_none());
+
+        queue.release_expired(Instant::now() + Duration::from_secs(31));
+        assert_eq!(queue.messages.len(), 1);
+
+        queue.set_attributes(HashMap::from([(
+            "VisibilityTimeout".to_owned(),
+            "120".to_owned(),
+        )]));
+        assert_eq!(queue.visibility_timeout(), Duration::from_secs(120));
+        let (_, received) = queue.receive(None).unwrap();
+        assert_eq!(received.receive_count, 3);
+        queue.release_expired(Instant::now() + Duration::from_secs(31));
+        assert!(queue.messages.is_empty());
+    }
+
+    #[test]
+    fn test_receive_batch() {
+        let mut queue = Queue::new();
+        for id in ["one", "two", "three"] {
+            queue.push(Message::new(id, "message_body"));
+        }
+
+        let received = queue.receive_batch(2, Some(Duration::ZERO));
+        let ids: Vec<&str> = received.iter().map(|(_, m)| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["one", "two"]);
+
+        let received = queue.receive_batch(10, None);
+        assert_eq!(received.len(), 3);
+        assert!(queue.receive_batch(10, None).is_empty());
+    }
+
+    #[test]
+    fn test_push_delayed() {
+        let mut queue = Queue::new();
+        queue.push_delayed(
+            Message::new("delayed", "message_body"),
+            Duration::from_secs(10),
+        );
+        queue.push_delayed(Message::new("now", "message_body"), Duration::ZERO);
+        assert_eq!(queue.approximate_number_of_messages(), 1);
+        assert_eq!(queue.approximate_number_of_messages_delayed(), 1);
+        assert!(queue.next_visible_at().is_some());
+
+        let (_, received) = queue.receive(None).unwrap();
+        assert_eq!(received.id, "now");
+        assert!(queue.receive(None).is_none());
+
+        queue.release_expired(Instant::now() + Duration::from_secs(11));
+        assert_eq!(
+            queue.messages.front().map(|m| m.id.as_str()),
+            Some("delayed")
+        );
+        assert_eq!(queue.approximate_number_of_messages_delayed(), 0);
+
+        queue.set_attributes(HashMap::from([(
+            "DelaySeconds".to_owned(),
+            "45".to_owned(),
+        )]));
+        assert_eq!(queue.delay(), Duration::from_secs(45));
+    }
+
+    #[test]
+    fn test_drop_expired() {
+        let mut queue = Queue::new();
+        queue.set_attributes(HashMap::from([(
+            "MessageRetentionPeriod".to_owned(),
+            "60".to_owned(),
+        )]));
+        for id in ["one", "two", "three"] {
+            queue.push(Message::new(id, "message_body"));
+        }
+        queue.push_delayed(
+            Message::new("four", "message_body"),
+            Duration::from_secs(30),
+        );
+        queue.receive(None).unwrap();
+
+        assert_eq!(queue.drop_expired(Instant::now()), 0);
+        assert_eq!(
+            queue.drop_expired(Instant::now() + Duration::from_secs(61)),
+            4
+        );
+        assert!(queue.messages.is_empty());
+        assert!(queue.delayed.is_empty());
+        assert!(queue.in_flight.is_empty());
+    }
+
+    #[test]
+    fn test_next_visible_at() {
+        let mut queue = Queue::new();
+        queue.push(Message::new("one", "message_body"));
+        queue.push(Message::new("two", "message_body"));
+        assert_eq!(queue.next_visible_at(), None);
+
+        let before = Instant::now();
+        queue.receive(Some(Duration::from_secs(60))).unwrap();
+        queue.receive(Some(Duration::from_secs(10))).unwrap();
+        let next = queue.next_visible_at().unwrap();
+        assert!(next >= before + Duration::from_secs(10));
+        assert!(next < before + Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_fifo_message_groups() {
+        let mut queue = Queue::new();
+        queue.set_attributes(HashMap::from([("FifoQueue".to_owned(), "true".to_owned())]));
+        for (id, group) in [("a1", "a"), ("b1", "b"), ("a2", "a"), ("b2", "b")] {
+            let mut message = Message::new(id, "message_body");
+            message.message_group_id = Some(group.to_owned());
+            queue.push(message);
+        }
+
+        let ids = |received: Vec<(String, Message)>| -> Vec<String> {
+            received
+                .into_iter()
+                .map(|(_, message)| message.id)
+                .collect()
+        };
+
+        // One receive can take several messages of a group, in order
+        let (a1_handle, _) = queue.receive(None).unwrap();
+        assert_eq!(ids(queue.receive_batch(10, None)), vec!["b1", "b2"]);
+        // Group a is locked until a1 is deleted or visible again
+        assert!(queue.receive(None).is_none());
+
+        assert_eq!(queue.delete_message(&a1_handle), Ok(()));
+        assert_eq!(ids(queue.receive_batch(10, None)), vec!["a2"]);
+    }
+
+    #[test]
+    fn test_requeue_keeps_send_order() {
+        let mut queue = Queue::new();
+        queue.set_attributes(HashMap::from([("FifoQueue".to_owned(), "true".to_owned())]));
+        for id in ["one", "two", "three"] {
+            let mut message = Message::new(id, "message_body");
+            message.message_group_id = Some("group".to_owned());
+            queue.push(message);
+        }
+
+        let received = queue.receive_batch(2, None);
+        for (receipt_handle, _) in &received {
+            queue
+                .change_visibility(receipt_handle, Duration::ZERO)
+                .unwrap();
+        }
+        let order: Vec<&str> = queue.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(order, vec!["one", "two", "three"]);
+    }
+
+    #[test]
+    fn test_deduplication() {
+        let mut queue = Queue::new();
+        assert_eq!(queue.find_duplicate(None, "dedup"), None);
+
+        let mut message = Message::new("id", "message_body");
+        message.message_group_id = Some("group".to_owned());
+        message.sequence_number = Some(queue.next_sequence_number());
+        queue.record_deduplication("dedup", &message);
+        assert_eq!(
+            queue.find_duplicate(Some("other group"), "dedup"),
+            Some(("id".to_owned(), Some("00000000000000000001".to_owned())))
+        );
+        assert_eq!(queue.find_duplicate(Some("group"), "other"), None);
+
+        let key = (None, "dedup".to_owned());
+        queue.deduplication.get_mut(&key).unwrap().expires_at = Instant::now();
+        assert_eq!(queue.find_duplicate(Some("group"), "dedup"), None);
+        assert!(queue.deduplication.is_empty());
+    }
+
+    #[test]
+    fn test_deduplication_per_message_group() {
+        let mut queue = Queue::new();
+        queue.set_attributes(HashMap::from([
+            ("FifoQueue".to_owned(), "true".to_owned()),
+            ("DeduplicationScope".to_owned(), "messageGroup".to_owned()),
+        ]));
+
+        let mut message = Message::new("id", "message_body");
+        message.message_group_id = Some("group".to_owned());
+        queue.record_deduplication("dedup", &message);
+        assert_eq!(
+            queue.find_duplicate(Some("group"), "dedup"),
+            Some(("id".to_owned(), None))
+        );
+        assert_eq!(queue.find_duplicate(Some("other group"), "dedup"), None);
+    }
+
+    #[test]
+    fn test_acquire_send() {
+        let mut queue = Queue::new();
+        queue.set_attributes(HashMap::from([("FifoQueue".to_owned(), "true".to_owned())]));
+        let now = Instant::now();
+
+        let key = queue.throughput_key(Some("a"));
+        assert_eq!(key, None);
+        for _ in 0..MAX_FIFO_SENDS_PER_SECOND {
+            assert!(queue.acquire_send(&key, now));
+        }
+        assert!(!queue.acquire_send(&queue.throughput_key(Some("b")), now));
+        assert!(queue.acquire_send(&key, now + Duration::from_secs(1)));
+
+        queue.set_attributes(HashMap::from([(
+            "FifoThroughputLimit".to_owned(),
+            "perMessageGroupId".to_owned(),
+        )]));
+        let later = now + Duration::from_secs(2);
+        let key = queue.throughput_key(Some("a"));
+        assert_eq!(key, Some("a".to_owned()));
+        for _ in 0..MAX_FIFO_SENDS_PER_SECOND {
+            assert!(queue.acquire_send(&key, later));
+        }
+        assert!(!queue.acquire_send(&key, later));
+        assert!(queue.acquire_send(&queue.throughput_key(Some("b")), later));
+    }
+
+    #[test]
+    fn test_sequence_number() {
+        let mut queue = Queue::new();
+        queue.set_sequence_number(41);
+        assert_eq!(queue.sequence_numbers_left(), 0);
+
+        queue.reserve_sequence_numbers(50);
+        assert_eq!(queue.next_sequence_number(), "00000000000000000042");
+        assert_eq!(queue.next_sequence_number(), "00000000000000000043");
+        assert_eq!(queue.sequence_numbers_left(), 7);
+
+        queue.set_sequence_number(10);
+        assert_eq!(queue.reserved_sequence_number(), 50);
+        assert_eq!(queue.next_sequence_number(), "00000000000000000044");
+    }
+
+    #[test]
+    fn test_receipt_handles_are_unique() {
+        assert_ne!(new_receipt_handle("id"), new_receipt_handle("id"));
+    }
 }
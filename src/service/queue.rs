@@ -1,6 +1,10 @@
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 use std::collections::HashMap;
 
+/// Region and account every queue of the mock belongs to
+pub const REGION: &str = "us-east-1";
+pub const ACCOUNT_ID: &str = "000000000000";
+
 pub struct Queue<'a> {
     db_pool: &'a SqlitePool,
     hostname: &'a str,
@@ -11,6 +15,8 @@ pub struct QueueEntity {
     pub id: Option<i64>,
     pub name: String,
     pub queue_type: String,
+    /// Last sequence number reserved by a FIFO queue
+    pub sequence_number: Option<i64>,
     pub attributes: Option<HashMap<String, String>>,
     pub tags: Option<HashMap<String, String>>,
     pub created_at: Option<time::OffsetDateTime>,
@@ -18,13 +24,18 @@ pub struct QueueEntity {
 }
 
 impl QueueEntity {
+    /// Type of the queue, the stored type or the one derived from a `.fifo` suffix
     fn get_type(&self) -> String {
-        if self.queue_type.contains(".fifo"){
-            return "Fifo".to_string();
+        if self.queue_type == "Fifo" || self.queue_type.contains(".fifo") {
+            "Fifo".to_string()
         } else {
-            return "Standard".to_string();
+            "Standard".to_string()
         }
     }
+
+    pub fn is_fifo(&self) -> bool {
+        self.get_type() == "Fifo"
+    }
 }
 
 impl<'a> Queue<'a> {
@@ -32,15 +43,57 @@ impl<'a> Queue<'a> {
         Queue { db_pool, hostname }
     }
 
+    /// URL of a queue, prefixed by the owner account when one is given
+    pub fn queue_url(&self, name: &str, account_id: Option<&str>) -> String {
+        match account_id {
+            Some(account_id) => format!("{}/{}/{}", self.hostname, account_id, name),
+            None => format!("{}/{}", self.hostname, name),
+        }
+    }
+
+    /// ARN of a queue
+    pub fn queue_arn(&self, name: &str) -> String {
+        format!("arn:aws:sqs:{}:{}:{}", REGION, ACCOUNT_ID, name)
+    }
+
+    /// Get the attributes stored for a queue
+    pub async fn get_attributes(&self, queue_id: i64) -> anyhow::Result<HashMap<String, String>> {
+        let rows = sqlx::query!(
+            r#"SELECT `name`, `value` FROM `attributes` WHERE `queue_id` = ? ORDER BY `id`"#,
+            queue_id
+        )
+        .fetch_all(self.db_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.name, row.value)).collect())
+    }
+
     /// Create queue attributes in the database
     /// If the attribute exists, update the value
     /// Attributes come from the https://docs.aws.amazon.com/AWSSimpleQueueService/latest/APIReference/API_CreateQueue.html
     async fn create_attributes(
-        &self,
+        conn: &mut SqliteConnection,
         queue_id: i64,
         attributes: HashMap<String, String>,
     ) -> anyhow::Result<()> {
         for (key, value) in attributes {
+            let updated = sqlx::query!(
+                r#"
+                UPDATE `attributes` SET `value` = ?, `updated_at` = CURRENT_TIMESTAMP
+                WHERE `queue_id` = ? AND `name` = ?
+                "#,
+                value,
+                queue_id,
+                key
+            )
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            if updated > 0 {
+                continue;
+            }
+
             sqlx::query!(
                 r#"
                 INSERT INTO `attributes` (`queue_id`, `name`, `value`) 
@@ -50,18 +103,61 @@ impl<'a> Queue<'a> {
                 key,
                 value
             )
-            .execute(self.db_pool)
+            .execute(&mut *conn)
             .await?;
         }
 
         Ok(())
     }
 
+    /// Set attributes of an existing queue and mark the queue as modified, all or nothing
+    pub async fn set_attributes(
+        &self,
+        queue_id: i64,
+        attributes: HashMap<String, String>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.db_pool.begin().await?;
+
+        Self::create_attributes(&mut tx, queue_id, attributes).await?;
+
+        sqlx::query!(
+            r#"UPDATE `queues` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = ?"#,
+            queue_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     /// Create queue tags in the database
     /// If the tag exists, update the value
     /// Tags come from the https://docs.aws.amazon.com/AWSSimpleQueueService/latest/APIReference/API_CreateQueue.html
-    async fn create_tags(&self, queue_id: i64, tags: HashMap<String, String>) -> anyhow::Result<()> {
+    pub async fn create_tags(
+        &self,
+        queue_id: i64,
+        tags: HashMap<String, String>,
+    ) -> anyhow::Result<()> {
         for (key, value) in tags {
+            let updated = sqlx::query!(
+                r#"
+                UPDATE `tags` SET `value` = ?, `updated_at` = CURRENT_TIMESTAMP
+                WHERE `queue_id` = ? AND `name` = ?
+                "#,
+                value,
+                queue_id,
+                key
+            )
+            .execute(self.db_pool)
+            .await?
+            .rows_affected();
+
+            if updated > 0 {
+                continue;
+            }
+
             sqlx::query!(
                 r#"
                 INSERT INTO `tags` (`queue_id`, `name`, `value`) 
@@ -78,6 +174,33 @@ impl<'a> Queue<'a> {
         Ok(())
     }
 
+    /// Get the tags of a queue, keys are case sensitive
+    pub async fn get_tags(&self, queue_id: i64) -> anyhow::Result<HashMap<String, String>> {
+        let rows = sqlx::query!(
+            r#"SELECT `name`, `value` FROM `tags` WHERE `queue_id` = ? ORDER BY `id`"#,
+            queue_id
+        )
+        .fetch_all(self.db_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.name, row.value)).collect())
+    }
+
+    /// Remove tags from a queue, unknown keys are ignored
+    pub async fn delete_tags(&self, queue_id: i64, keys: Vec<String>) -> anyhow::Result<()> {
+        for key in keys {
+            sqlx::query!(
+                r#"DELETE FROM `tags` WHERE `queue_id` = ? AND `name` = ?"#,
+                queue_id,
+                key
+            )
+            .execute(self.db_pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
     /// Get queue in the database
     pub async fn create_queue(&self, queue: QueueEntity) -> anyhow::Result<String> {
         let queue_type = queue.get_type().clone();
@@ -94,14 +217,15 @@ impl<'a> Queue<'a> {
         .last_insert_rowid();
 
         if let Some(attributes) = queue.attributes {
-            self.create_attributes(inserted_id, attributes).await?;
+            let mut conn = self.db_pool.acquire().await?;
+            Self::create_attributes(&mut conn, inserted_id, attributes).await?;
         }
 
         if let Some(tags) = queue.tags {
             self.create_tags(inserted_id, tags).await?;
         }
 
-        return Ok(inserted_id.to_string());
+        Ok(inserted_id.to_string())
     }
 
     pub async fn list_queue(
@@ -116,12 +240,115 @@ impl<'a> Queue<'a> {
 
         let mut queue_urls: Vec<String> = Vec::new();
         rows.iter().for_each(|row| {
-            queue_urls.push(format!("{}/{}", self.hostname, &row.name));
+            queue_urls.push(self.queue_url(&row.name, None));
         });
 
         Ok(queue_urls)
     }
 
+    /// Get a queue by name, attributes and tags are not loaded
+    pub async fn get_queue(&self, name: &str) -> anyhow::Result<Option<QueueEntity>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT `id`, `name`, `type` AS queue_type, `sequence_number`, `created_at` AS "created_at: time::OffsetDateTime", `updated_at` AS "updated_at: time::OffsetDateTime"
+            FROM `queues`
+            WHERE `name` = ?
+            ORDER BY `id`
+            LIMIT 1
+            "#,
+            name
+        )
+        .fetch_optional(self.db_pool)
+        .await?;
+
+        Ok(row.map(|row| QueueEntity {
+            id: Some(row.id),
+            name: row.name,
+            queue_type: row.queue_type,
+            sequence_number: Some(row.sequence_number),
+            attributes: None,
+            tags: None,
+            created_at: Some(row.created_at),
+            updated_at: Some(row.updated_at),
+        }))
+    }
+
+    /// Get every queue together with its attributes
+    pub async fn list_queue_entities(&self) -> anyhow::Result<Vec<QueueEntity>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT `id`, `name`, `type` AS queue_type, `sequence_number`, `created_at` AS "created_at: time::OffsetDateTime", `updated_at` AS "updated_at: time::OffsetDateTime"
+            FROM `queues`
+            ORDER BY `id`
+            "#
+        )
+        .fetch_all(self.db_pool)
+        .await?;
+
+        let mut queues = Vec::with_capacity(rows.len());
+        for row in rows {
+            queues.push(QueueEntity {
+                id: Some(row.id),
+                name: row.name,
+                queue_type: row.queue_type,
+                sequence_number: Some(row.sequence_number),
+                attributes: Some(self.get_attributes(row.id).await?),
+                tags: None,
+                created_at: Some(row.created_at),
+                updated_at: Some(row.updated_at),
+            });
+        }
+
+        Ok(queues)
+    }
+
+    /// Store the last sequence number reserved by a FIFO queue, it never goes backwards
+    pub async fn set_sequence_number(
+        &self,
+        name: &str,
+        sequence_number: i64,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"UPDATE `queues` SET `sequence_number` = ? WHERE `name` = ? AND `sequence_number` < ?"#,
+            sequence_number,
+            name,
+            sequence_number
+        )
+        .execute(self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a queue by name together with its attributes and tags in one transaction
+    /// Returns false when there is no queue with the given name
+    pub async fn delete_queue(&self, name: &str) -> anyhow::Result<bool> {
+        let mut tx = self.db_pool.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM `attributes` WHERE `queue_id` IN (SELECT `id` FROM `queues` WHERE `name` = ?)"#,
+            name
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"DELETE FROM `tags` WHERE `queue_id` IN (SELECT `id` FROM `queues` WHERE `name` = ?)"#,
+            name
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let deleted = sqlx::query!(r#"DELETE FROM `queues` WHERE `name` = ?"#, name)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(deleted > 0)
+    }
+
     #[allow(dead_code)]
     pub fn send_message(&self) {
         todo!()